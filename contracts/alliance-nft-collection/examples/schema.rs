@@ -1,7 +1,5 @@
 use alliance_nft_packages::{
-    instantiate::InstantiateCollectionMsg,
-    execute::ExecuteCollectionMsg,
-    query::QueryCollectionMsg
+    execute::ExecuteCollectionMsg, instantiate::InstantiateCollectionMsg, query::QueryCollectionMsg,
 };
 use cosmwasm_schema::write_api;
 
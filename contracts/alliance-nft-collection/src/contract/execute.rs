@@ -1,31 +1,45 @@
-use alliance_nft_packages::eris::{validate_dao_treasury_share, AssetInfoExt};
-use alliance_nft_packages::execute::{UpdateConfigMsg, UpdateRewardsCallbackMsg};
-use alliance_nft_packages::state::ALLOWED_DENOM;
+use alliance_nft_packages::eris::{
+    validate_dao_treasury_share, validate_keeper_fee, AssetInfoExt, Hub,
+};
+use alliance_nft_packages::execute::{SwitchLstMsg, UpdateConfigMsg};
+use alliance_nft_packages::state::{
+    Config, Harvest, LstSwitch, MinterExtension, PendingHarvest, RewardIndex, Trait, Unbonding,
+    ALLOWED_DENOM, FORGED_TRAIT,
+};
 use cosmwasm_std::{
-    entry_point, to_json_binary, Addr, Binary, CosmosMsg, Decimal, Order, SubMsg, Uint128, WasmMsg,
+    coin, entry_point, to_json_binary, Addr, Binary, CosmosMsg, Decimal256, Order, QuerierWrapper,
+    Reply, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response};
 use cw721::Cw721Query;
-use cw_asset::AssetInfoBase;
+use cw721_base::state::TokenInfo;
+use cw721_base::ContractError as CW721BaseError;
+use cw_asset::{Asset, AssetInfo, AssetInfoBase};
+use std::collections::BTreeMap;
 use terra_proto_rs::alliance::alliance::{MsgClaimDelegationRewards, MsgRedelegate, MsgUndelegate};
 use terra_proto_rs::{
     alliance::alliance::MsgDelegate, cosmos::base::v1beta1::Coin, traits::Message,
 };
 
 use crate::state::{
-    reduce_val_stake, upsert_val, BROKEN_NFTS, CONFIG, NFT_BALANCE_CLAIMED, NUM_ACTIVE_NFTS,
-    REWARD_BALANCE, VALS,
+    activate_nft, add_assets, add_harvest, add_unbonding, checkpoint_nft_asset_rewards,
+    checkpoint_nft_rewards, nft_weight, reduce_val_stake, remove_matured_unbondings, remove_nft,
+    reward_recipient, reweight_nft, upsert_val, ACTIVE_WEIGHT, ASSET_REWARDS_HELD,
+    ASSET_REWARD_INDEXES, BROKEN_NFTS, CONFIG, FIXED_NFT_WEIGHTS, FORGE_METADATA, HARVEST_ID,
+    LAST_HARVEST, LST_SWITCH, LST_SWITCH_HARVEST_ID, NFT_WEIGHTS, NUM_ACTIVE_NFTS, PENDING_HARVEST,
+    REWARD_INDEX, REWARD_RECIPIENTS, VALS,
 };
 use alliance_nft_packages::{
     errors::ContractError,
     execute::{
-        AllianceDelegateMsg, AllianceRedelegateMsg, AllianceUndelegateMsg, ExecuteCollectionMsg,
-        MintMsg,
+        AllianceDelegateMsg, AllianceRedelegateMsg, AllianceUndelegateMsg, BreakPayout,
+        ExecuteCollectionMsg, MintMsg,
     },
-    AllianceNftCollection,
+    AllianceNftCollection, Extension,
 };
 
-use super::reply::CLAIM_REWARD_ERROR_REPLY_ID;
+use super::query::query_alliance_delegation;
+use super::reply::{BOND_REPLY_ID, CLAIM_REWARD_ERROR_REPLY_ID, SWITCH_LST_REPLY_ID};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
@@ -44,27 +58,73 @@ pub fn execute(
             try_alliance_redelegate(deps, env, info, msg)
         }
 
-        ExecuteCollectionMsg::AllianceClaimRewards {} => try_alliance_claim_rewards(deps, env),
-        ExecuteCollectionMsg::StakeRewardsCallback {} => try_stake_reward_callback(deps, env, info),
-        ExecuteCollectionMsg::UpdateRewardsCallback(msg) => {
-            try_update_reward_callback(deps, env, info, msg)
+        ExecuteCollectionMsg::Rebalance {} => try_rebalance(deps, env, info),
+        ExecuteCollectionMsg::AllianceClaimRewards {} => {
+            try_alliance_claim_rewards(deps, env, info)
+        }
+        ExecuteCollectionMsg::StakeRewardsCallback { keeper } => {
+            try_stake_reward_callback(deps, env, info, keeper)
         }
 
-        ExecuteCollectionMsg::BreakNft(token_id) => try_breaknft(deps, env, info, parent, token_id),
+        ExecuteCollectionMsg::BreakNft { token_id, payout } => {
+            try_breaknft(deps, env, info, parent, token_id, payout)
+        }
+        ExecuteCollectionMsg::BreakNfts { token_ids, payout } => {
+            try_breaknfts(deps, env, info, parent, token_ids, payout)
+        }
+        ExecuteCollectionMsg::ClaimRewards { token_ids } => {
+            try_claim_rewards(deps, env, info, parent, token_ids)
+        }
+        ExecuteCollectionMsg::RepairNft { token_id } => {
+            try_repair_nft(deps, env, info, parent, token_id)
+        }
+        ExecuteCollectionMsg::Forge { token_ids } => try_forge(deps, env, info, parent, token_ids),
+        ExecuteCollectionMsg::AppendForgeMetadata(metadata) => {
+            try_append_forge_metadata(deps, info, parent, metadata)
+        }
+        ExecuteCollectionMsg::RemoveForgeMetadata { token_ids } => {
+            try_remove_forge_metadata(deps, info, token_ids)
+        }
+        ExecuteCollectionMsg::RefreshNftWeights { token_ids } => {
+            try_refresh_nft_weights(deps, parent, token_ids)
+        }
+        ExecuteCollectionMsg::SetRewardRecipient {
+            token_id,
+            recipient,
+        } => try_set_reward_recipient(deps, env, info, parent, token_id, recipient),
+        ExecuteCollectionMsg::TransferNft { ref token_id, .. }
+        | ExecuteCollectionMsg::SendNft { ref token_id, .. } => {
+            let token_id = token_id.clone();
+            try_transfer_nft(deps, env, info, parent, token_id, msg)
+        }
         ExecuteCollectionMsg::Mint(mint_msg) => try_mint(deps, info, parent, mint_msg),
+        ExecuteCollectionMsg::Burn { token_id } => try_burn(deps, env, info, parent, token_id),
         ExecuteCollectionMsg::ChangeOwner(new_owner) => try_change_owner(deps, info, new_owner),
-        ExecuteCollectionMsg::UpdateConfig(msg) => try_update_config(deps, info, msg),
+        ExecuteCollectionMsg::UpdateConfig(msg) => try_update_config(deps, env, info, msg),
+        ExecuteCollectionMsg::SwitchLst(msg) => try_switch_lst(deps, env, info, msg),
+        ExecuteCollectionMsg::SyncDelegations {} => try_sync_delegations(deps, env),
+        ExecuteCollectionMsg::SweepUndistributedRewards {} => {
+            try_sweep_undistributed_rewards(deps, info)
+        }
 
         _ => Ok(parent.execute(deps, env, info, msg.into())?),
     }
 }
 
-fn try_alliance_claim_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+fn try_alliance_claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
-    let num_of_active = NUM_ACTIVE_NFTS.load(deps.storage)?;
-    if num_of_active == 0 {
-        return Err(ContractError::NoActiveNfts {});
+    if let Some(last_harvest) = LAST_HARVEST.may_load(deps.storage)? {
+        let next_harvest = last_harvest.plus_seconds(cfg.harvest_interval);
+        if env.block.time < next_harvest {
+            return Err(ContractError::HarvestTooEarly(next_harvest));
+        }
     }
+    LAST_HARVEST.save(deps.storage, &env.block.time)?;
+    remove_matured_unbondings(deps.storage, env.block.time)?;
 
     let validators = VALS
         .range(deps.storage, None, None, Order::Ascending)
@@ -87,7 +147,14 @@ fn try_alliance_claim_rewards(deps: DepsMut, env: Env) -> Result<Response, Contr
         })
         .collect();
 
-    let msg = get_stake_reward_callback_msg(env);
+    // the caller is paid the keeper fee once the harvested rewards are staked
+    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_json_binary(&ExecuteCollectionMsg::StakeRewardsCallback {
+            keeper: Some(info.sender),
+        })?,
+        funds: vec![],
+    });
 
     Ok(Response::new()
         .add_attributes(vec![("action", "update_rewards")])
@@ -98,7 +165,7 @@ fn try_alliance_claim_rewards(deps: DepsMut, env: Env) -> Result<Response, Contr
 fn get_stake_reward_callback_msg(env: Env) -> CosmosMsg {
     CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteCollectionMsg::StakeRewardsCallback {}).unwrap(),
+        msg: to_json_binary(&ExecuteCollectionMsg::StakeRewardsCallback { keeper: None }).unwrap(),
         funds: vec![],
     })
 }
@@ -107,85 +174,157 @@ fn try_stake_reward_callback(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    keeper: Option<Addr>,
 ) -> Result<Response, ContractError> {
     authorize_execution(env.contract.address.clone(), info.sender)?;
     let config = CONFIG.load(deps.storage)?;
 
+    // rewards in the other reward assets are distributed without staking them
+    let asset_reward_msgs = distribute_asset_rewards(deps.storage, &deps.querier, &env, &config)?;
+
     // check if there are tokens to stake
     let tokens_to_stake = AssetInfoBase::native(ALLOWED_DENOM)
         .query_balance(&deps.querier, env.contract.address.clone())?;
 
     if tokens_to_stake.is_zero() {
-        return Ok(Response::new().add_attributes(vec![
-            ("action", "stake_reward_callback"),
-            ("result", "nothing to stake"),
-        ]));
+        return Ok(Response::new()
+            .add_attributes(vec![
+                ("action", "stake_reward_callback"),
+                ("result", "nothing to stake"),
+            ])
+            .add_messages(asset_reward_msgs));
     }
 
-    // create stake / bond message
-    let stake_msg = config
-        .lst_hub
-        .bond_msg(ALLOWED_DENOM, tokens_to_stake.u128(), None)?;
-
-    // prepare update rewards callback, by querying the current total lsts in the contract.
-    let previous_lst_balance = config
-        .lst_asset_info
-        .query_balance(&deps.querier, env.contract.address.clone())?;
-
-    let update_rewards_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteCollectionMsg::UpdateRewardsCallback(
-            UpdateRewardsCallbackMsg {
-                previous_lst_balance,
-            },
-        ))?,
-        funds: vec![],
-    });
+    // create stake / bond message, the LST minted is read from the reply of the hub
+    // so that LST sent to the contract for any other reason is not counted as rewards.
+    let stake_msg =
+        config
+            .lst_provider
+            .bond_msg(&config.lst_hub, ALLOWED_DENOM, tokens_to_stake.u128())?;
+    PENDING_HARVEST.save(
+        deps.storage,
+        &PendingHarvest {
+            luna_bonded: tokens_to_stake,
+            keeper,
+        },
+    )?;
 
     Ok(Response::new()
         .add_attributes(vec![("action", "stake_reward_callback")])
-        .add_message(stake_msg)
-        .add_message(update_rewards_msg))
+        .add_messages(asset_reward_msgs)
+        .add_submessage(SubMsg::reply_on_success(stake_msg, BOND_REPLY_ID)))
 }
 
-fn try_update_reward_callback(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: UpdateRewardsCallbackMsg,
-) -> Result<Response, ContractError> {
-    authorize_execution(env.contract.address.clone(), info.sender)?;
+// Accounts the balance of each additional reward asset that is not held for the NFTs yet
+// as new rewards, sending the DAO treasury share and splitting the rest between active NFTs.
+fn distribute_asset_rewards(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let active_weight = ACTIVE_WEIGHT.load(storage)?;
+    let mut msgs = vec![];
+    for asset_info in config.reward_assets.iter() {
+        let balance = asset_info.query_balance(querier, env.contract.address.clone())?;
+        let held = ASSET_REWARDS_HELD
+            .may_load(storage, asset_info)?
+            .unwrap_or_default();
+        let mut rewards_collected = balance.saturating_sub(held);
+        if rewards_collected.is_zero() {
+            continue;
+        }
+
+        let treasury_amount = config.dao_treasury_share * rewards_collected;
+        if !treasury_amount.is_zero() {
+            rewards_collected = rewards_collected.checked_sub(treasury_amount)?;
+            msgs.push(
+                asset_info
+                    .clone()
+                    .with_balance(treasury_amount)
+                    .transfer_msg(config.dao_treasury_address.clone())?,
+            );
+        }
+
+        let mut reward_index = ASSET_REWARD_INDEXES
+            .may_load(storage, asset_info)?
+            .unwrap_or_default();
+        reward_index.distribute(rewards_collected, active_weight)?;
+        ASSET_REWARD_INDEXES.save(storage, asset_info, &reward_index)?;
+        ASSET_REWARDS_HELD.save(storage, asset_info, &(held + rewards_collected))?;
+    }
+    Ok(msgs)
+}
+
+pub fn reply_on_bond(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    let events = reply
+        .result
+        .into_result()
+        .map_err(|err| ContractError::Std(StdError::generic_err(err)))?
+        .events;
     let config = CONFIG.load(deps.storage)?;
+    let harvested = config.lst_provider.parse_bonded_amount(&events)?;
+    let pending = PENDING_HARVEST.load(deps.storage)?;
+    PENDING_HARVEST.remove(deps.storage);
 
-    let current_balance = config
-        .lst_asset_info
-        .query_balance(&deps.querier, env.contract.address.clone())?;
-    let previous_balance = msg.previous_lst_balance;
-    let mut rewards_collected = current_balance - previous_balance;
+    let mut rewards_collected = harvested;
 
     // if there is an lst_treasury_share, then the specified amount will be sent to the dao treasury.
     let mut msgs = vec![];
+    let mut treasury_amount = Uint128::zero();
     if !config.dao_treasury_share.is_zero() {
-        let treasury_amount = config.dao_treasury_share * rewards_collected;
+        treasury_amount = config.dao_treasury_share * harvested;
         if !treasury_amount.is_zero() {
             rewards_collected = rewards_collected.checked_sub(treasury_amount)?;
             msgs.push(
                 config
                     .lst_asset_info
+                    .clone()
                     .with_balance(treasury_amount)
                     .transfer_msg(config.dao_treasury_address)?,
             )
         }
     }
 
-    let num_of_active_nfts = NUM_ACTIVE_NFTS.load(deps.storage)?;
-    let average_reward = rewards_collected / Uint128::from(num_of_active_nfts);
-    REWARD_BALANCE.update(deps.storage, |balance| -> Result<_, ContractError> {
-        Ok(balance + average_reward)
-    })?;
+    // the keeper that triggered the harvest is paid the keeper fee
+    let mut keeper_amount = Uint128::zero();
+    if let Some(keeper) = pending.keeper {
+        keeper_amount = config.keeper_fee * harvested;
+        if !keeper_amount.is_zero() {
+            rewards_collected = rewards_collected.checked_sub(keeper_amount)?;
+            msgs.push(
+                config
+                    .lst_asset_info
+                    .with_balance(keeper_amount)
+                    .transfer_msg(keeper)?,
+            )
+        }
+    }
+
+    let active_weight = ACTIVE_WEIGHT.load(deps.storage)?;
+    let mut reward_index = REWARD_INDEX.load(deps.storage)?;
+    let index_increment = reward_index.distribute(rewards_collected, active_weight)?;
+    REWARD_INDEX.save(deps.storage, &reward_index)?;
+
+    add_harvest(
+        deps.storage,
+        &Harvest {
+            time: env.block.time,
+            luna_bonded: pending.luna_bonded,
+            lst_received: harvested,
+            treasury_amount,
+            keeper_fee: keeper_amount,
+            index_increment,
+            active_weight,
+        },
+    )?;
 
     Ok(Response::new()
-        .add_attributes(vec![("action", "update_rewards_callback")])
+        .add_attributes(vec![
+            ("action", "bond_reply"),
+            ("lst_received", &harvested.to_string()),
+            ("keeper_fee", &keeper_amount.to_string()),
+        ])
         .add_messages(msgs))
 }
 
@@ -201,20 +340,7 @@ fn try_alliance_delegate(
     let mut cosmos_msg: Vec<CosmosMsg> = Vec::new();
 
     for del in msg.delegations.iter() {
-        let msg = CosmosMsg::Stargate {
-            type_url: "/alliance.alliance.MsgDelegate".to_string(),
-            value: Binary::from(
-                MsgDelegate {
-                    delegator_address: env.contract.address.to_string(),
-                    validator_address: del.validator.clone(),
-                    amount: Some(Coin {
-                        denom: cfg.asset_denom.clone(),
-                        amount: del.amount.to_string(),
-                    }),
-                }
-                .encode_to_vec(),
-            ),
-        };
+        let msg = alliance_delegate_msg(&env, &cfg.asset_denom, &del.validator, del.amount);
 
         upsert_val(deps.storage, del.validator.clone(), del.amount)?;
         cosmos_msg.push(msg);
@@ -228,6 +354,40 @@ fn try_alliance_delegate(
         .add_messages(cosmos_msg))
 }
 
+// Anyone can sync the stake tracked per validator with the Alliance module,
+// so that slashing or failed messages do not leave VALS out of date
+fn try_sync_delegations(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let vals = VALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, Uint128)>>>()?;
+
+    let mut res = Response::default().add_attribute("action", "sync_delegations");
+    for (validator, tracked) in vals {
+        let actual = query_alliance_delegation(
+            &deps.querier,
+            &env.contract.address,
+            &validator,
+            &cfg.asset_denom,
+        )?;
+        if actual == tracked {
+            continue;
+        }
+
+        if actual.is_zero() {
+            VALS.remove(deps.storage, validator.clone());
+        } else {
+            VALS.save(deps.storage, validator.clone(), &actual)?;
+        }
+        res = res.add_attributes(vec![
+            ("validator", validator),
+            ("tracked", tracked.to_string()),
+            ("actual", actual.to_string()),
+        ]);
+    }
+    Ok(res)
+}
+
 fn try_alliance_undelegate(
     deps: DepsMut,
     env: Env,
@@ -240,6 +400,8 @@ fn try_alliance_undelegate(
     if msg.undelegations.is_empty() {
         return Err(ContractError::EmptyDelegation {});
     }
+    remove_matured_unbondings(deps.storage, env.block.time)?;
+
     let mut cosmos_msg = vec![];
     for delegation in msg.undelegations {
         let undelegate_msg = MsgUndelegate {
@@ -255,7 +417,20 @@ fn try_alliance_undelegate(
             value: Binary::from(undelegate_msg.encode_to_vec()),
         };
         cosmos_msg.push(msg);
-        reduce_val_stake(deps.storage, delegation.validator, delegation.amount)?;
+        reduce_val_stake(
+            deps.storage,
+            delegation.validator.clone(),
+            delegation.amount,
+        )?;
+        add_unbonding(
+            deps.storage,
+            &Unbonding {
+                validator: delegation.validator,
+                amount: delegation.amount,
+                initiated_at: env.block.time,
+                completion_time: env.block.time.plus_seconds(cfg.unbonding_period),
+            },
+        )?;
     }
     let msg = get_stake_reward_callback_msg(env);
     cosmos_msg.push(msg);
@@ -280,19 +455,13 @@ fn try_alliance_redelegate(
     for redelegation in msg.redelegations {
         let src_validator = redelegation.src_validator;
         let dst_validator = redelegation.dst_validator;
-        let redelegate_msg = MsgRedelegate {
-            amount: Some(Coin {
-                denom: cfg.asset_denom.clone(),
-                amount: redelegation.amount.to_string(),
-            }),
-            delegator_address: env.contract.address.to_string(),
-            validator_src_address: src_validator.to_string(),
-            validator_dst_address: dst_validator.to_string(),
-        };
-        let msg = CosmosMsg::Stargate {
-            type_url: "/alliance.alliance.MsgRedelegate".to_string(),
-            value: Binary::from(redelegate_msg.encode_to_vec()),
-        };
+        let msg = alliance_redelegate_msg(
+            &env,
+            &cfg.asset_denom,
+            &src_validator,
+            &dst_validator,
+            redelegation.amount,
+        );
         cosmos_msg.push(msg);
         upsert_val(deps.storage, dst_validator, redelegation.amount)?;
         reduce_val_stake(deps.storage, src_validator, redelegation.amount)?;
@@ -304,64 +473,645 @@ fn try_alliance_redelegate(
         .add_messages(cosmos_msg))
 }
 
+// Computes the delegations and redelegations that move the stake tracked in VALS to the
+// validator targets of the config. The idle virtual tokens are delegated before any
+// stake is redelegated, and each surplus is redelegated to the deficits in order.
+fn try_rebalance(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    authorize_execution(cfg.owner.clone(), info.sender)?;
+
+    if cfg.validator_targets.is_empty() {
+        return Err(ContractError::ValidatorTargetsNotSet {});
+    }
+
+    let vals = VALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<BTreeMap<String, Uint128>>>()?;
+    let mut idle = deps
+        .querier
+        .query_balance(&env.contract.address, &cfg.asset_denom)?
+        .amount;
+    let total = vals.values().fold(idle, |total, stake| total + stake);
+    let total_weight: u64 = cfg.validator_targets.values().sum();
+
+    // the stake lost when rounding down the targets goes to the first validator
+    let mut targets: BTreeMap<String, Uint128> = cfg
+        .validator_targets
+        .iter()
+        .map(|(validator, weight)| {
+            (
+                validator.clone(),
+                total.multiply_ratio(*weight, total_weight),
+            )
+        })
+        .collect();
+    let leftover = targets
+        .values()
+        .try_fold(total, |leftover, target| leftover.checked_sub(*target))?;
+    if let Some(first) = targets.values_mut().next() {
+        *first += leftover;
+    }
+
+    let mut surpluses = vals
+        .iter()
+        .filter_map(|(validator, stake)| {
+            let target = targets.get(validator).copied().unwrap_or_default();
+            (*stake > target).then(|| (validator.clone(), *stake - target))
+        })
+        .collect::<Vec<_>>()
+        .into_iter();
+    let deficits = targets.iter().filter_map(|(validator, target)| {
+        let stake = vals.get(validator).copied().unwrap_or_default();
+        (*target > stake).then(|| (validator.clone(), *target - stake))
+    });
+
+    let mut cosmos_msg = vec![];
+    let mut surplus = surpluses.next();
+    for (dst_validator, mut deficit) in deficits {
+        let amount = idle.min(deficit);
+        if !amount.is_zero() {
+            cosmos_msg.push(alliance_delegate_msg(
+                &env,
+                &cfg.asset_denom,
+                &dst_validator,
+                amount,
+            ));
+            upsert_val(deps.storage, dst_validator.clone(), amount)?;
+            idle -= amount;
+            deficit -= amount;
+        }
+
+        while !deficit.is_zero() {
+            let Some((src_validator, available)) = surplus.as_mut() else {
+                break;
+            };
+            let amount = (*available).min(deficit);
+            cosmos_msg.push(alliance_redelegate_msg(
+                &env,
+                &cfg.asset_denom,
+                src_validator,
+                &dst_validator,
+                amount,
+            ));
+            upsert_val(deps.storage, dst_validator.clone(), amount)?;
+            reduce_val_stake(deps.storage, src_validator.clone(), amount)?;
+            *available -= amount;
+            deficit -= amount;
+            if available.is_zero() {
+                surplus = surpluses.next();
+            }
+        }
+    }
+
+    if !cosmos_msg.is_empty() {
+        cosmos_msg.push(get_stake_reward_callback_msg(env));
+    }
+    Ok(Response::new()
+        .add_attributes(vec![("action", "rebalance")])
+        .add_messages(cosmos_msg))
+}
+
+fn alliance_delegate_msg(env: &Env, denom: &str, validator: &str, amount: Uint128) -> CosmosMsg {
+    CosmosMsg::Stargate {
+        type_url: "/alliance.alliance.MsgDelegate".to_string(),
+        value: Binary::from(
+            MsgDelegate {
+                delegator_address: env.contract.address.to_string(),
+                validator_address: validator.to_string(),
+                amount: Some(Coin {
+                    denom: denom.to_string(),
+                    amount: amount.to_string(),
+                }),
+            }
+            .encode_to_vec(),
+        ),
+    }
+}
+
+fn alliance_redelegate_msg(
+    env: &Env,
+    denom: &str,
+    src_validator: &str,
+    dst_validator: &str,
+    amount: Uint128,
+) -> CosmosMsg {
+    CosmosMsg::Stargate {
+        type_url: "/alliance.alliance.MsgRedelegate".to_string(),
+        value: Binary::from(
+            MsgRedelegate {
+                amount: Some(Coin {
+                    denom: denom.to_string(),
+                    amount: amount.to_string(),
+                }),
+                delegator_address: env.contract.address.to_string(),
+                validator_src_address: src_validator.to_string(),
+                validator_dst_address: dst_validator.to_string(),
+            }
+            .encode_to_vec(),
+        ),
+    }
+}
+
 fn try_breaknft(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     parent: AllianceNftCollection,
     token_id: String,
+    payout: BreakPayout,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
-    let owner_res = parent.owner_of(deps.as_ref(), env, token_id.clone(), false)?;
-    let owner = deps.api.addr_validate(&owner_res.owner)?;
-    authorize_execution(owner.clone(), info.sender)?;
+    let owner = authorize_token_execution(deps.as_ref(), &env, &info, &parent, &token_id)?;
 
-    BROKEN_NFTS.update(
-        deps.storage,
-        token_id.clone(),
-        |b| -> Result<_, ContractError> {
-            match b {
-                Some(b) => {
-                    if b {
-                        Err(ContractError::AlreadyBroken {})
-                    } else {
-                        Ok(true)
-                    }
+    let (rewards_claimable, asset_rewards) = break_token(deps.storage, token_id.clone())?;
+    let recipient = reward_recipient(deps.storage, token_id.clone(), owner)?;
+
+    let res = Response::default().add_attributes(vec![
+        ("action", "break_nft"),
+        ("token_id", token_id.as_str()),
+        ("rewards", rewards_claimable.to_string().as_str()),
+    ]);
+    add_break_payout(
+        res,
+        &cfg,
+        &payout,
+        rewards_claimable,
+        asset_rewards,
+        &recipient,
+    )
+}
+
+fn try_breaknfts(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_ids: Vec<String>,
+    payout: BreakPayout,
+) -> Result<Response, ContractError> {
+    if token_ids.is_empty() {
+        return Err(ContractError::EmptyTokenIds {});
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let mut rewards_claimable = Uint128::zero();
+    let mut payouts = Payouts::new();
+    let mut attributes = vec![("action".to_string(), "break_nfts".to_string())];
+    for token_id in token_ids {
+        let owner = authorize_token_execution(deps.as_ref(), &env, &info, &parent, &token_id)?;
+
+        let (rewards, assets) = break_token(deps.storage, token_id.clone())?;
+        rewards_claimable += rewards;
+        let recipient = reward_recipient(deps.storage, token_id.clone(), owner)?;
+        add_payout(&mut payouts, recipient, rewards, assets);
+        attributes.push(("token_id".to_string(), token_id));
+        attributes.push(("rewards".to_string(), rewards.to_string()));
+    }
+
+    let mut res = Response::default()
+        .add_attributes(attributes)
+        .add_attribute("total_rewards", rewards_claimable);
+    for (recipient, (rewards, asset_rewards)) in payouts {
+        res = add_break_payout(res, &cfg, &payout, rewards, asset_rewards, &recipient)?;
+    }
+    Ok(res)
+}
+
+// Pays out the rewards of broken NFTs, the LST rewards are either transferred or queued
+// for unbonding on the hub, the recipient withdraws the LUNA once the unbonding finished
+fn add_break_payout(
+    res: Response,
+    cfg: &Config,
+    payout: &BreakPayout,
+    rewards: Uint128,
+    asset_rewards: Vec<Asset>,
+    recipient: &Addr,
+) -> Result<Response, ContractError> {
+    match payout {
+        BreakPayout::Lst => add_reward_transfers(res, cfg, rewards, asset_rewards, recipient),
+        BreakPayout::Unbond => {
+            let mut res =
+                add_reward_transfers(res, cfg, Uint128::zero(), asset_rewards, recipient)?;
+            if !rewards.is_zero() {
+                let lst = cfg.lst_asset_info.clone().with_balance(rewards);
+                res = res
+                    .add_message(cfg.lst_provider.queue_unbond_msg(
+                        &cfg.lst_hub,
+                        lst,
+                        recipient.to_string(),
+                    )?)
+                    .add_attribute("payout", "unbond");
+            }
+            Ok(res)
+        }
+    }
+}
+
+// Flags the token as broken, removing it from the active NFTs,
+// and returns the rewards it accrued until now
+fn break_token(
+    storage: &mut dyn Storage,
+    token_id: String,
+) -> Result<(Uint128, Vec<Asset>), ContractError> {
+    BROKEN_NFTS.update(storage, token_id.clone(), |b| -> Result<_, ContractError> {
+        match b {
+            Some(b) => {
+                if b {
+                    Err(ContractError::AlreadyBroken {})
+                } else {
+                    Ok(true)
                 }
-                None => Ok(true),
             }
-        },
-    )?;
+            None => Ok(true),
+        }
+    })?;
+
+    let rewards_claimable = checkpoint_nft_rewards(storage, token_id.clone())?;
+    let asset_rewards = checkpoint_nft_asset_rewards(storage, token_id.clone())?;
+    let weight = nft_weight(storage, token_id)?;
+    NUM_ACTIVE_NFTS.update(storage, |n| -> Result<_, ContractError> { Ok(n - 1) })?;
+    ACTIVE_WEIGHT.update(storage, |w| -> Result<_, ContractError> { Ok(w - weight) })?;
+    Ok((rewards_claimable, asset_rewards))
+}
+
+// Burns the NFT through the cw721 base contract. An NFT that is not broken yet
+// is broken first and its rewards are paid out, then its reward state is removed.
+fn try_burn(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let owner = authorize_token_execution(deps.as_ref(), &env, &info, &parent, &token_id)?;
+    let recipient = reward_recipient(deps.storage, token_id.clone(), owner)?;
+
+    let broken = BROKEN_NFTS
+        .may_load(deps.storage, token_id.clone())?
+        .unwrap_or(false);
+    let (rewards, asset_rewards) = if broken {
+        (Uint128::zero(), vec![])
+    } else {
+        break_token(deps.storage, token_id.clone())?
+    };
+    let res =
+        burn_nft(deps, env, info, &parent, token_id)?.add_attribute("rewards", rewards.to_string());
+    add_reward_transfers(res, &cfg, rewards, asset_rewards, &recipient)
+}
+
+// Removes the reward state of the token and burns it through the cw721 base contract
+fn burn_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: &AllianceNftCollection,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    remove_nft(deps.storage, token_id.clone())?;
+    Ok(parent.execute(
+        deps,
+        env,
+        info,
+        ExecuteCollectionMsg::Burn { token_id }.into(),
+    )?)
+}
+
+// Reactivates a broken NFT after the repair fee is paid to the DAO treasury.
+// The NFT only accrues the rewards distributed after the repair.
+fn try_repair_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    authorize_token_execution(deps.as_ref(), &env, &info, &parent, &token_id)?;
+    let fee = cfg.repair_fee.ok_or(ContractError::RepairDisabled {})?;
+
+    let broken = BROKEN_NFTS
+        .may_load(deps.storage, token_id.clone())?
+        .unwrap_or(false);
+    if !broken {
+        return Err(ContractError::NotBroken {});
+    }
 
-    let rewards_claimed = NFT_BALANCE_CLAIMED.load(deps.storage, token_id.to_string())?;
-    let average_rewards = REWARD_BALANCE.load(deps.storage)?;
-    let rewards_claimable = average_rewards - rewards_claimed;
+    // native fees are sent along with the message, cw20 fees are transferred
+    // from the sender, who has to increase the allowance of the contract first
+    let fee_msg = match &fee.info {
+        AssetInfo::Native(denom) => {
+            if info.funds.len() != 1 || info.funds[0] != coin(fee.amount.u128(), denom) {
+                return Err(ContractError::InvalidFunds(fee.to_string()));
+            }
+            fee.transfer_msg(cfg.dao_treasury_address)?
+        }
+        _ => {
+            if !info.funds.is_empty() {
+                return Err(ContractError::InvalidFunds(fee.to_string()));
+            }
+            fee.transfer_from_msg(info.sender, cfg.dao_treasury_address)?
+        }
+    };
+    BROKEN_NFTS.remove(deps.storage, token_id.clone());
+    activate_nft(deps.storage, token_id.clone())?;
 
-    NFT_BALANCE_CLAIMED.save(deps.storage, token_id.clone(), &average_rewards)?;
-    NUM_ACTIVE_NFTS.update(deps.storage, |n| -> Result<_, ContractError> { Ok(n - 1) })?;
-    if rewards_claimable.is_zero() {
-        Ok(Response::default().add_attributes(vec![
-            ("action", "break_nft"),
+    Ok(Response::default()
+        .add_message(fee_msg)
+        .add_attributes(vec![
+            ("action", "repair_nft"),
             ("token_id", token_id.as_str()),
-            ("rewards", rewards_claimable.to_string().as_str()),
+            ("fee", fee.to_string().as_str()),
         ]))
+}
+
+// Burns the broken NFTs of the sender and mints a new active NFT to the sender with the
+// next metadata of the forge pool, adding a trait that lists the burned token ids
+fn try_forge(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let forge_inputs = cfg.forge_inputs.ok_or(ContractError::ForgeDisabled {})?;
+    if token_ids.len() != forge_inputs as usize {
+        return Err(ContractError::InvalidForgeInputs(forge_inputs));
+    }
+
+    for (i, token_id) in token_ids.iter().enumerate() {
+        if token_ids[..i].contains(token_id) {
+            return Err(ContractError::DuplicateTokenId(token_id.clone()));
+        }
+        let token = parent.tokens.load(deps.storage, token_id)?;
+        authorize_execution(token.owner, info.sender.clone())?;
+        // active NFTs have to be broken first to pay out their rewards
+        let broken = BROKEN_NFTS
+            .may_load(deps.storage, token_id.clone())?
+            .unwrap_or(false);
+        if !broken {
+            return Err(ContractError::NotBroken {});
+        }
+    }
+
+    // entries whose token_id got minted in the meantime are dropped from the pool
+    let (token_id, mut extension) = loop {
+        let (token_id, extension) = FORGE_METADATA
+            .range(deps.storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?
+            .ok_or(ContractError::NoForgeMetadata {})?;
+        FORGE_METADATA.remove(deps.storage, token_id.clone());
+        if !parent.tokens.has(deps.storage, &token_id) {
+            break (token_id, extension);
+        }
+    };
+
+    let mut res = Response::default();
+    for burned_id in &token_ids {
+        let burn_res = burn_nft(
+            deps.branch(),
+            env.clone(),
+            info.clone(),
+            &parent,
+            burned_id.clone(),
+        )?;
+        res = res.add_attributes(burn_res.attributes);
+    }
+
+    extension
+        .attributes
+        .get_or_insert_with(Vec::new)
+        .push(Trait {
+            display_type: None,
+            trait_type: FORGED_TRAIT.to_string(),
+            value: token_ids.join(","),
+        });
+
+    let weight = weight_from_traits(&cfg, &extension)?;
+    NFT_WEIGHTS.save(deps.storage, token_id.clone(), &weight)?;
+    let token = TokenInfo {
+        owner: info.sender.clone(),
+        approvals: vec![],
+        token_uri: None,
+        extension,
+    };
+    parent
+        .tokens
+        .update(deps.storage, &token_id, |old| match old {
+            Some(_) => Err(CW721BaseError::Claimed {}),
+            None => Ok(token),
+        })?;
+    parent.increment_tokens(deps.storage)?;
+    activate_nft(deps.storage, token_id.clone())?;
+
+    Ok(res.add_attributes(vec![
+        ("action", "forge"),
+        ("owner", info.sender.as_str()),
+        ("token_id", token_id.as_str()),
+        ("forged_from", token_ids.join(",").as_str()),
+    ]))
+}
+
+fn try_append_forge_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    metadata: Vec<MinterExtension>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    authorize_execution(cfg.owner, info.sender)?;
+
+    let count = metadata.len();
+    for MinterExtension {
+        token_id,
+        extension,
+    } in metadata
+    {
+        if FORGE_METADATA.has(deps.storage, token_id.clone())
+            || parent.tokens.has(deps.storage, &token_id)
+        {
+            return Err(ContractError::AlreadyExists(token_id));
+        }
+        FORGE_METADATA.save(deps.storage, token_id, &extension)?;
+    }
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "append_forge_metadata"),
+        ("count", count.to_string().as_str()),
+    ]))
+}
+
+fn try_remove_forge_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    authorize_execution(cfg.owner, info.sender)?;
+
+    for token_id in &token_ids {
+        FORGE_METADATA.remove(deps.storage, token_id.clone());
+    }
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "remove_forge_metadata"),
+        ("token_ids", token_ids.join(",").as_str()),
+    ]))
+}
+
+fn try_claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    if token_ids.is_empty() {
+        return Err(ContractError::EmptyTokenIds {});
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let mut rewards_claimable = Uint128::zero();
+    let mut payouts = Payouts::new();
+    let mut attributes = vec![("action".to_string(), "claim_rewards".to_string())];
+    for token_id in token_ids {
+        let owner = authorize_token_execution(deps.as_ref(), &env, &info, &parent, &token_id)?;
+
+        // broken NFTs do not accumulate rewards and were already paid out on break
+        if BROKEN_NFTS
+            .may_load(deps.storage, token_id.clone())?
+            .unwrap_or(false)
+        {
+            return Err(ContractError::AlreadyBroken {});
+        }
+
+        let rewards = checkpoint_nft_rewards(deps.storage, token_id.clone())?;
+        rewards_claimable += rewards;
+        let assets = checkpoint_nft_asset_rewards(deps.storage, token_id.clone())?;
+        let recipient = reward_recipient(deps.storage, token_id.clone(), owner)?;
+        add_payout(&mut payouts, recipient, rewards, assets);
+        attributes.push(("token_id".to_string(), token_id));
+        attributes.push(("rewards".to_string(), rewards.to_string()));
+    }
+
+    let res = Response::default()
+        .add_attributes(attributes)
+        .add_attribute("total_rewards", rewards_claimable);
+    add_payout_transfers(res, &cfg, payouts)
+}
+
+// Transfers or sends the NFT through the cw721 base contract, which checks the approvals.
+// If the config settles rewards on transfer, the pending rewards are paid out to the
+// previous owner first, so that they do not pass to the new owner.
+fn try_transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_id: String,
+    msg: ExecuteCollectionMsg,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let owner = parent.tokens.load(deps.storage, &token_id)?.owner;
+    let recipient = reward_recipient(deps.storage, token_id.clone(), owner)?;
+    // the reward recipient was chosen by the previous owner
+    REWARD_RECIPIENTS.remove(deps.storage, token_id.clone());
+
+    let broken = BROKEN_NFTS
+        .may_load(deps.storage, token_id.clone())?
+        .unwrap_or(false);
+    let settle = cfg.settle_rewards_on_transfer && !broken;
+    let (rewards, asset_rewards) = if settle {
+        (
+            checkpoint_nft_rewards(deps.storage, token_id.clone())?,
+            checkpoint_nft_asset_rewards(deps.storage, token_id)?,
+        )
     } else {
-        let send_msg = cfg
-            .lst_asset_info
-            .with_balance(rewards_claimable)
-            .transfer_msg(owner.to_string())?;
+        (Uint128::zero(), vec![])
+    };
 
-        // let share = Decimal::from_ratio(rewards_claimable, average_rewards) ;
-        // foreach additional claim, query token balance of the additional claim
+    let res = parent.execute(deps, env, info, msg.into())?;
+    if !settle {
+        return Ok(res);
+    }
+    let res = res.add_attribute("settled_rewards", rewards.to_string());
+    add_reward_transfers(res, &cfg, rewards, asset_rewards, &recipient)
+}
 
-        Ok(Response::default()
-            .add_message(send_msg)
-            .add_attributes(vec![
-                ("action", "break_nft"),
-                ("token_id", token_id.as_str()),
-                ("rewards", rewards_claimable.to_string().as_str()),
-            ]))
+fn try_set_reward_recipient(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: AllianceNftCollection,
+    token_id: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let owner_res = parent.owner_of(deps.as_ref(), env, token_id.clone(), false)?;
+    let owner = deps.api.addr_validate(&owner_res.owner)?;
+    authorize_execution(owner.clone(), info.sender)?;
+
+    let recipient = match recipient {
+        Some(recipient) => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            REWARD_RECIPIENTS.save(deps.storage, token_id.clone(), &recipient)?;
+            recipient
+        }
+        None => {
+            REWARD_RECIPIENTS.remove(deps.storage, token_id.clone());
+            owner
+        }
+    };
+    Ok(Response::default().add_attributes(vec![
+        ("action", "set_reward_recipient"),
+        ("token_id", token_id.as_str()),
+        ("recipient", recipient.as_str()),
+    ]))
+}
+
+// Rewards of a batch of NFTs summed up per reward recipient
+type Payouts = BTreeMap<Addr, (Uint128, Vec<Asset>)>;
+
+fn add_payout(payouts: &mut Payouts, recipient: Addr, rewards: Uint128, assets: Vec<Asset>) {
+    let (total_rewards, total_assets) = payouts.entry(recipient).or_default();
+    *total_rewards += rewards;
+    add_assets(total_assets, assets);
+}
+
+fn add_payout_transfers(
+    mut res: Response,
+    cfg: &Config,
+    payouts: Payouts,
+) -> Result<Response, ContractError> {
+    for (recipient, (rewards, asset_rewards)) in payouts {
+        res = add_reward_transfers(res, cfg, rewards, asset_rewards, &recipient)?;
     }
+    Ok(res)
+}
+
+// Adds a single transfer message per reward asset to the response
+fn add_reward_transfers(
+    mut res: Response,
+    cfg: &Config,
+    rewards: Uint128,
+    asset_rewards: Vec<Asset>,
+    recipient: &Addr,
+) -> Result<Response, ContractError> {
+    if !rewards.is_zero() {
+        res = res.add_message(
+            cfg.lst_asset_info
+                .clone()
+                .with_balance(rewards)
+                .transfer_msg(recipient.to_string())?,
+        );
+    }
+    for asset in asset_rewards {
+        res = res
+            .add_message(asset.transfer_msg(recipient.to_string())?)
+            .add_attribute("asset_rewards", asset.to_string());
+    }
+    Ok(res)
 }
 
 fn try_mint(
@@ -371,9 +1121,19 @@ fn try_mint(
     mint_msg: MintMsg,
 ) -> Result<Response, ContractError> {
     // authorization is checked in the parent contract
-    NUM_ACTIVE_NFTS.update(deps.storage, |n| -> Result<_, ContractError> { Ok(n + 1) })?;
-    let reward_balance = REWARD_BALANCE.load(deps.storage)?;
-    NFT_BALANCE_CLAIMED.save(deps.storage, mint_msg.token_id.clone(), &reward_balance)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let weight = match mint_msg.weight {
+        Some(weight) => weight,
+        None => weight_from_traits(&cfg, &mint_msg.extension)?,
+    };
+    if weight == 0 {
+        return Err(ContractError::InvalidNftWeight(mint_msg.token_id));
+    }
+    NFT_WEIGHTS.save(deps.storage, mint_msg.token_id.clone(), &weight)?;
+    if mint_msg.weight.is_some() {
+        FIXED_NFT_WEIGHTS.save(deps.storage, mint_msg.token_id.clone(), &true)?;
+    }
+    activate_nft(deps.storage, mint_msg.token_id.clone())?;
     parent
         .mint(
             deps,
@@ -386,6 +1146,65 @@ fn try_mint(
         .map_err(ContractError::FromContractError)
 }
 
+// Reads the reward weight from the value of the configured weight trait,
+// NFTs are weighted equally while no weight trait is configured
+fn weight_from_traits(cfg: &Config, extension: &Extension) -> Result<u64, ContractError> {
+    let weight_trait = match &cfg.weight_trait {
+        Some(weight_trait) => weight_trait,
+        None => return Ok(1),
+    };
+    let value = extension
+        .attributes
+        .iter()
+        .flatten()
+        .find(|t| &t.trait_type == weight_trait)
+        .map(|t| t.value.clone());
+
+    match value {
+        Some(value) => value
+            .parse::<u64>()
+            .ok()
+            .filter(|weight| *weight > 0)
+            .ok_or(ContractError::InvalidNftWeight(value)),
+        None => Ok(1),
+    }
+}
+
+fn try_refresh_nft_weights(
+    deps: DepsMut,
+    parent: AllianceNftCollection,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    if token_ids.is_empty() {
+        return Err(ContractError::EmptyTokenIds {});
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let mut payouts = Payouts::new();
+    let mut res = Response::default().add_attribute("action", "refresh_nft_weights");
+    for token_id in token_ids {
+        let token = parent.tokens.load(deps.storage, &token_id)?;
+        // weights set explicitly at mint are final and broken NFTs do not share the rewards anymore
+        let is_broken = BROKEN_NFTS
+            .may_load(deps.storage, token_id.clone())?
+            .unwrap_or(false);
+        if is_broken || FIXED_NFT_WEIGHTS.has(deps.storage, token_id.clone()) {
+            continue;
+        }
+
+        let weight = weight_from_traits(&cfg, &token.extension)?;
+        // rewards that cannot stay pending with a lower weight are paid out
+        let (rewards, asset_rewards) = reweight_nft(deps.storage, token_id.clone(), weight)?;
+        let recipient = reward_recipient(deps.storage, token_id.clone(), token.owner)?;
+        add_payout(&mut payouts, recipient, rewards, asset_rewards);
+        res = res.add_attributes(vec![("token_id", token_id), ("weight", weight.to_string())]);
+        if !rewards.is_zero() {
+            res = res.add_attribute("rewards", rewards);
+        }
+    }
+    add_payout_transfers(res, &cfg, payouts)
+}
+
 fn try_change_owner(
     deps: DepsMut,
     info: MessageInfo,
@@ -407,6 +1226,7 @@ fn try_change_owner(
 
 fn try_update_config(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: UpdateConfigMsg,
 ) -> Result<Response, ContractError> {
@@ -417,11 +1237,242 @@ fn try_update_config(
         cfg.dao_treasury_share = validate_dao_treasury_share(dao_treasury_share)?;
     }
 
+    if let Some(reward_assets) = msg.reward_assets {
+        let mut assets: Vec<AssetInfo> = vec![];
+        for asset in reward_assets {
+            let asset = asset.check(deps.api, None)?;
+            // uluna is staked in the LST and the virtual staking token is never a reward
+            if asset == cfg.lst_asset_info
+                || asset == AssetInfo::native(ALLOWED_DENOM)
+                || asset == AssetInfo::native(cfg.asset_denom.clone())
+                || assets.contains(&asset)
+            {
+                return Err(ContractError::InvalidRewardAsset(asset.to_string()));
+            }
+            // the reward index of a new asset starts at zero for every NFT, and the balance
+            // the contract already holds is not distributed, only what is received from now on
+            if !ASSET_REWARD_INDEXES.has(deps.storage, &asset) {
+                ASSET_REWARD_INDEXES.save(deps.storage, &asset, &RewardIndex::default())?;
+                let balance = asset.query_balance(&deps.querier, env.contract.address.clone())?;
+                ASSET_REWARDS_HELD.save(deps.storage, &asset, &balance)?;
+            }
+            assets.push(asset);
+        }
+        cfg.reward_assets = assets;
+    }
+
+    // an empty trait clears the weight trait
+    if let Some(weight_trait) = msg.weight_trait {
+        cfg.weight_trait = Some(weight_trait).filter(|t| !t.is_empty());
+    }
+
+    if let Some(unbonding_period) = msg.unbonding_period {
+        cfg.unbonding_period = unbonding_period;
+    }
+
+    if let Some(keeper_fee) = msg.keeper_fee {
+        cfg.keeper_fee = validate_keeper_fee(keeper_fee)?;
+    }
+
+    if let Some(harvest_interval) = msg.harvest_interval {
+        cfg.harvest_interval = harvest_interval;
+    }
+
+    if let Some(settle_rewards_on_transfer) = msg.settle_rewards_on_transfer {
+        cfg.settle_rewards_on_transfer = settle_rewards_on_transfer;
+    }
+
+    if let Some(repair_fee) = msg.repair_fee {
+        let repair_fee = repair_fee.check(deps.api, None)?;
+        // the virtual staking token is only held by the contract
+        if repair_fee.amount.is_zero()
+            || repair_fee.info == AssetInfo::native(cfg.asset_denom.clone())
+        {
+            return Err(ContractError::InvalidRepairFee(repair_fee.to_string()));
+        }
+        cfg.repair_fee = Some(repair_fee);
+    }
+
+    if let Some(forge_inputs) = msg.forge_inputs {
+        // a single broken NFT would be turned into an active one without a fee
+        if forge_inputs < 2 {
+            return Err(ContractError::InvalidForgeInputs(forge_inputs));
+        }
+        cfg.forge_inputs = Some(forge_inputs);
+    }
+
+    if let Some(validator_targets) = msg.validator_targets {
+        if let Some((validator, _)) = validator_targets.iter().find(|(_, w)| **w == 0) {
+            return Err(ContractError::InvalidValidatorTarget(validator.clone()));
+        }
+        cfg.validator_targets = validator_targets;
+    }
+
     CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::default().add_attributes(vec![("action", "try_update_config")]))
 }
 
+// Swaps all held LST into the new LST and switches the config to it. The amount received
+// is measured in the reply of the swap, which rescales the rewards of the NFTs accordingly.
+fn try_switch_lst(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: SwitchLstMsg,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    authorize_execution(cfg.owner.clone(), info.sender)?;
+
+    let lst_asset_info = msg.lst_asset_info.check(deps.api, None)?;
+    if lst_asset_info == cfg.lst_asset_info
+        || lst_asset_info == AssetInfo::native(ALLOWED_DENOM)
+        || lst_asset_info == AssetInfo::native(cfg.asset_denom.clone())
+        || cfg.reward_assets.contains(&lst_asset_info)
+    {
+        return Err(ContractError::InvalidLst(lst_asset_info.to_string()));
+    }
+
+    let previous_lst_asset_info = cfg.lst_asset_info.clone();
+    let swapped =
+        previous_lst_asset_info.query_balance(&deps.querier, env.contract.address.clone())?;
+    let balance_before =
+        lst_asset_info.query_balance(&deps.querier, env.contract.address.clone())?;
+
+    cfg.lst_hub = Hub(deps.api.addr_validate(&msg.lst_hub_address)?);
+    cfg.lst_asset_info = lst_asset_info;
+    cfg.lst_provider = msg.lst_provider;
+    CONFIG.save(deps.storage, &cfg)?;
+    // the harvests from now on are paid in the new LST, also when nothing is swapped
+    let next_harvest_id = HARVEST_ID.may_load(deps.storage)?.unwrap_or_default();
+    LST_SWITCH_HARVEST_ID.save(deps.storage, &next_harvest_id)?;
+
+    let res = Response::new().add_attributes(vec![
+        ("action", "switch_lst"),
+        ("lst_asset_info", &cfg.lst_asset_info.to_string()),
+        ("swapped", &swapped.to_string()),
+    ]);
+    if swapped.is_zero() {
+        return Ok(res);
+    }
+
+    let swap_contract = deps.api.addr_validate(&msg.swap_contract)?;
+    let swap_msg = match &previous_lst_asset_info {
+        AssetInfo::Native(denom) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_contract.to_string(),
+            msg: msg.swap_msg,
+            funds: vec![coin(swapped.u128(), denom)],
+        }),
+        _ => previous_lst_asset_info
+            .clone()
+            .with_balance(swapped)
+            .send_msg(swap_contract, msg.swap_msg)?,
+    };
+    LST_SWITCH.save(
+        deps.storage,
+        &LstSwitch {
+            previous_lst_asset_info,
+            swapped,
+            balance_before,
+            min_received: msg.min_received,
+        },
+    )?;
+
+    Ok(res.add_submessage(SubMsg::reply_on_success(swap_msg, SWITCH_LST_REPLY_ID)))
+}
+
+pub fn reply_on_switch_lst(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let switch = LST_SWITCH.load(deps.storage)?;
+    LST_SWITCH.remove(deps.storage);
+
+    let left = switch
+        .previous_lst_asset_info
+        .query_balance(&deps.querier, env.contract.address.clone())?;
+    if !left.is_zero() {
+        return Err(ContractError::LstSwitchIncomplete(left));
+    }
+
+    let received = cfg
+        .lst_asset_info
+        .query_balance(&deps.querier, env.contract.address)?
+        .checked_sub(switch.balance_before)?;
+    if received.is_zero() || received < switch.min_received {
+        return Err(ContractError::LstSwitchMinReceived(
+            received,
+            switch.min_received,
+        ));
+    }
+
+    // the rewards held for the NFTs are converted at the ratio of the swap
+    let ratio = Decimal256::from_ratio(received, switch.swapped);
+    let mut reward_index = REWARD_INDEX.load(deps.storage)?;
+    reward_index.rescale(ratio)?;
+    REWARD_INDEX.save(deps.storage, &reward_index)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "switch_lst_reply"),
+        ("received", &received.to_string()),
+        ("ratio", &ratio.to_string()),
+    ]))
+}
+
+fn try_sweep_undistributed_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    authorize_execution(cfg.owner.clone(), info.sender)?;
+
+    let mut assets = vec![];
+    let mut reward_index = REWARD_INDEX.load(deps.storage)?;
+    let amount = reward_index.take_remainder()?;
+    REWARD_INDEX.save(deps.storage, &reward_index)?;
+    if !amount.is_zero() {
+        assets.push(cfg.lst_asset_info.clone().with_balance(amount));
+    }
+
+    let asset_reward_indexes = ASSET_REWARD_INDEXES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(AssetInfo, RewardIndex)>>>()?;
+    for (asset_info, mut reward_index) in asset_reward_indexes {
+        let amount = reward_index.take_remainder()?;
+        if amount.is_zero() {
+            continue;
+        }
+        ASSET_REWARD_INDEXES.save(deps.storage, &asset_info, &reward_index)?;
+        ASSET_REWARDS_HELD.update(deps.storage, &asset_info, |held| -> StdResult<_> {
+            Ok(held.unwrap_or_default().checked_sub(amount)?)
+        })?;
+        assets.push(asset_info.with_balance(amount));
+    }
+
+    let mut res =
+        Response::default().add_attributes(vec![("action", "sweep_undistributed_rewards")]);
+    for asset in assets {
+        res = res
+            .add_message(asset.transfer_msg(cfg.dao_treasury_address.clone())?)
+            .add_attribute("swept", asset.to_string());
+    }
+    Ok(res)
+}
+
+// Authorises the owner of the token, an approved spender of the token or an operator
+// of the owner as in the cw721 base contract, returning the owner of the token
+fn authorize_token_execution(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    parent: &AllianceNftCollection,
+    token_id: &str,
+) -> Result<Addr, ContractError> {
+    let token = parent.tokens.load(deps.storage, token_id)?;
+    parent
+        .check_can_send(deps, env, info, &token)
+        .map_err(|_| ContractError::Unauthorized(info.sender.clone(), token.owner.clone()))?;
+    Ok(token.owner)
+}
+
 fn authorize_execution(owner: Addr, sender: Addr) -> Result<Response, ContractError> {
     if sender != owner {
         return Err(ContractError::Unauthorized(sender, owner));
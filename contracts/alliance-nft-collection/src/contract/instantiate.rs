@@ -2,12 +2,12 @@ use alliance_nft_packages::{
     eris::{validate_dao_treasury_share, Hub},
     errors::ContractError,
     instantiate::InstantiateCollectionMsg,
-    state::Config,
+    state::{Config, RewardIndex, DEFAULT_UNBONDING_PERIOD},
     AllianceNftCollection,
 };
 use cosmwasm_std::{
-    entry_point, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Reply, Response, StdError, SubMsg,
-    Uint128,
+    entry_point, Binary, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Reply, Response, StdError,
+    SubMsg,
 };
 use cw2::set_contract_version;
 use cw_utils::parse_instantiate_response_data;
@@ -20,7 +20,8 @@ use terra_proto_rs::{
     traits::Message,
 };
 
-use crate::state::{CONFIG, NUM_ACTIVE_NFTS, REWARD_BALANCE};
+use crate::state::{ACTIVE_WEIGHT, CONFIG, NUM_ACTIVE_NFTS, REWARD_INDEX};
+use std::collections::BTreeMap;
 
 use super::reply::INSTANTIATE_REPLY_ID;
 
@@ -49,11 +50,22 @@ pub fn instantiate(
             lst_hub: Hub(deps.api.addr_validate(&msg.lst_hub_address)?),
             dao_treasury_share: validate_dao_treasury_share(msg.dao_treasury_share)?,
             lst_asset_info: msg.lst_asset_info.check(deps.api, None)?,
+            lst_provider: msg.lst_provider.clone(),
+            reward_assets: vec![],
+            weight_trait: None,
+            unbonding_period: DEFAULT_UNBONDING_PERIOD,
+            validator_targets: BTreeMap::new(),
+            keeper_fee: Decimal::zero(),
+            harvest_interval: 0,
+            settle_rewards_on_transfer: false,
+            repair_fee: None,
+            forge_inputs: None,
         },
     )?;
 
-    REWARD_BALANCE.save(deps.storage, &Uint128::zero())?;
+    REWARD_INDEX.save(deps.storage, &RewardIndex::default())?;
     NUM_ACTIVE_NFTS.save(deps.storage, &0)?;
+    ACTIVE_WEIGHT.save(deps.storage, &0)?;
 
     let create_denom_req: CosmosMsg = CosmosMsg::Stargate {
         type_url: "/osmosis.tokenfactory.v1beta1.MsgCreateDenom".to_string(),
@@ -1,14 +1,18 @@
 use alliance_nft_packages::eris::{validate_dao_treasury_share, Hub};
 use alliance_nft_packages::migrate::Version110MigrateData;
-use alliance_nft_packages::state::ALLOWED_DENOM;
+use alliance_nft_packages::state::{RewardIndex, ALLOWED_DENOM};
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{DepsMut, Env, Response};
+use cosmwasm_std::{Decimal256, DepsMut, Env, Response, Uint128};
 use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Item;
 
 use alliance_nft_packages::{errors::ContractError, migrate::MigrateMsg};
 use cw_asset::AssetInfo;
 
-use crate::state::{CONFIG, REWARD_BALANCE};
+use crate::state::{ACTIVE_WEIGHT, CONFIG, NUM_ACTIVE_NFTS, REWARD_INDEX};
+
+// Average rewards per NFT used until version 1.2.0, replaced by REWARD_INDEX
+const LEGACY_REWARD_BALANCE: Item<Uint128> = Item::new("rb");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
@@ -31,6 +35,10 @@ fn try_migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
         };
     }
 
+    if version == "1.2.0" {
+        return migrate_to_1_2_0(deps, version);
+    }
+
     Ok(Response::new()
         .add_attribute("method", "try_migrate")
         .add_attribute("version", contract_version.version))
@@ -55,10 +63,10 @@ fn migrate_to_1_1_0(
     // this allows us to keep the contract simplified and not work with callback messages to check how much ampLUNA was really received.
     // total_ustake = 200k, total_uluna = 300k, rewards_current = 10k -> rewards_in_lst = 6.66k
     let lst_hub_state = config.lst_hub.query_state(&deps.querier)?;
-    let rewards_current = REWARD_BALANCE.load(deps.storage)?;
+    let rewards_current = LEGACY_REWARD_BALANCE.load(deps.storage)?;
     let rewards_in_lst =
         rewards_current.multiply_ratio(lst_hub_state.total_ustake, lst_hub_state.total_uluna);
-    REWARD_BALANCE.save(deps.storage, &rewards_in_lst)?;
+    LEGACY_REWARD_BALANCE.save(deps.storage, &rewards_in_lst)?;
 
     // create bond message
     let balance_native =
@@ -77,3 +85,31 @@ fn migrate_to_1_1_0(
         .add_attribute("version", version)
         .add_message(bond_msg))
 }
+
+fn migrate_to_1_2_0(deps: DepsMut, version: String) -> Result<Response, ContractError> {
+    // the integer average of rewards per NFT becomes the starting point of the Decimal256 index.
+    // The remainders dropped by previous harvests are already lost, so the remainder starts at zero.
+    let reward_balance = LEGACY_REWARD_BALANCE.load(deps.storage)?;
+    REWARD_INDEX.save(
+        deps.storage,
+        &RewardIndex {
+            index: Decimal256::from_ratio(reward_balance, 1u128),
+            remainder: Decimal256::zero(),
+            scale: Decimal256::one(),
+        },
+    )?;
+    LEGACY_REWARD_BALANCE.remove(deps.storage);
+
+    // NFT_BALANCE_CLAIMED (nb) is not rewritten: the Uint128 checkpoints are serialized as the same
+    // string as a Decimal256 of equal value, so each one is converted when it is read the next time.
+    // Rewriting all of them in the migration would not fit into a single transaction.
+
+    // existing NFTs have no stored weight and count with a weight of 1 each
+    let num_of_active_nfts = NUM_ACTIVE_NFTS.load(deps.storage)?;
+    ACTIVE_WEIGHT.save(deps.storage, &num_of_active_nfts)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate_to_1_2_0")
+        .add_attribute("version", version)
+        .add_attribute("reward_index", reward_balance))
+}
@@ -1,13 +1,43 @@
 use alliance_nft_packages::errors::ContractError;
-use cosmwasm_std::{entry_point, to_json_binary, Addr, QuerierWrapper, Uint128};
+use std::str::FromStr;
+
+use cosmwasm_std::{
+    entry_point, to_json_binary, to_json_vec, Addr, ContractResult, Decimal, Decimal256, Empty,
+    Order, QuerierWrapper, QueryRequest, StdError, SystemResult, Uint128,
+};
 use cosmwasm_std::{Binary, Deps, Env, StdResult};
 use cw721::{AllNftInfoResponse, Approval, NftInfoResponse, OwnerOfResponse};
 use cw721_base::state::{Approval as BaseApproval, TokenInfo};
+use cw_asset::Asset;
+use cw_storage_plus::Bound;
+use terra_proto_rs::alliance::alliance::{
+    QueryAllianceDelegationRequest, QueryAllianceDelegationResponse,
+    QueryAllianceDelegationRewardsRequest, QueryAllianceDelegationRewardsResponse,
+};
+use terra_proto_rs::cosmos::base::v1beta1::Coin;
+use terra_proto_rs::traits::Message;
 
+use alliance_nft_packages::query::{
+    AssetRewardState, BrokenNftsResponse, DelegationDriftResponse, HarvestHistoryResponse,
+    NftWeightResponse, OwnerPortfolioResponse, PortfolioNft, RewardRateResponse,
+    RewardRecipientResponse, RewardStateResponse, SimulateBreakResponse, SimulateHarvestResponse,
+    SimulatedBreak, UnbondRequestsResponse, UnbondingsResponse, ValidatorDrift, ValidatorStake,
+    ValidatorsResponse,
+};
 use alliance_nft_packages::state::{Config, Trait, ALLOWED_DENOM};
 use alliance_nft_packages::{query::QueryCollectionMsg, AllianceNftCollection, Extension};
 
-use crate::state::{BROKEN_NFTS, CONFIG, NFT_BALANCE_CLAIMED, REWARD_BALANCE};
+use crate::state::{
+    add_assets, nft_weight, query_nft_asset_rewards, query_nft_rewards, reward_recipient,
+    ACTIVE_WEIGHT, ASSET_REWARDS_HELD, ASSET_REWARD_INDEXES, BROKEN_NFTS, CONFIG, HARVESTS,
+    LAST_HARVEST, LST_SWITCH_HARVEST_ID, NUM_ACTIVE_NFTS, REWARD_INDEX, UNBONDINGS, VALS,
+};
+
+// Settings for pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryCollectionMsg) -> StdResult<Binary> {
@@ -27,6 +57,52 @@ pub fn query(deps: Deps, env: Env, msg: QueryCollectionMsg) -> StdResult<Binary>
             token_id,
             include_expired,
         )?),
+        QueryCollectionMsg::NftWeight { token_id } => {
+            to_json_binary(&query_nft_weight(deps, parent, token_id)?)
+        }
+        QueryCollectionMsg::RewardRecipient { token_id } => {
+            to_json_binary(&query_reward_recipient(deps, parent, token_id)?)
+        }
+        QueryCollectionMsg::OwnerPortfolio {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_owner_portfolio(
+            deps,
+            parent,
+            owner,
+            start_after,
+            limit,
+        )?),
+        QueryCollectionMsg::SimulateBreak { token_ids } => {
+            to_json_binary(&query_simulate_break(deps, parent, token_ids)?)
+        }
+        QueryCollectionMsg::SimulateHarvest {} => {
+            to_json_binary(&query_simulate_harvest(deps, env)?)
+        }
+        QueryCollectionMsg::Validators { start_after, limit } => {
+            to_json_binary(&query_validators(deps, start_after, limit)?)
+        }
+        QueryCollectionMsg::DelegationDrift { start_after, limit } => {
+            to_json_binary(&query_delegation_drift(deps, env, start_after, limit)?)
+        }
+        QueryCollectionMsg::HarvestHistory {
+            start_before,
+            limit,
+        } => to_json_binary(&query_harvest_history(deps, start_before, limit)?),
+        QueryCollectionMsg::RewardRate { window_seconds } => {
+            to_json_binary(&query_reward_rate(deps, env, window_seconds)?)
+        }
+        QueryCollectionMsg::Unbondings {} => to_json_binary(&query_unbondings(deps, env)?),
+        QueryCollectionMsg::UnbondRequests {
+            user,
+            start_after,
+            limit,
+        } => to_json_binary(&query_unbond_requests(deps, user, start_after, limit)?),
+        QueryCollectionMsg::RewardState {} => to_json_binary(&query_reward_state(deps)?),
+        QueryCollectionMsg::BrokenNfts { start_after, limit } => {
+            to_json_binary(&query_broken_nfts(deps, start_after, limit)?)
+        }
         _ => parent.query(deps, env, msg.into()),
     }
 }
@@ -37,6 +113,374 @@ fn query_config(deps: Deps) -> StdResult<Config> {
     Ok(res)
 }
 
+fn query_reward_recipient(
+    deps: Deps,
+    parent: AllianceNftCollection,
+    token_id: String,
+) -> StdResult<RewardRecipientResponse> {
+    let owner = parent.tokens.load(deps.storage, &token_id)?.owner;
+    Ok(RewardRecipientResponse {
+        recipient: reward_recipient(deps.storage, token_id, owner)?,
+    })
+}
+
+fn query_nft_weight(
+    deps: Deps,
+    parent: AllianceNftCollection,
+    token_id: String,
+) -> StdResult<NftWeightResponse> {
+    // fails for tokens that do not exist
+    parent.tokens.load(deps.storage, &token_id)?;
+
+    Ok(NftWeightResponse {
+        weight: nft_weight(deps.storage, token_id)?,
+        total_active_weight: ACTIVE_WEIGHT.load(deps.storage)?,
+    })
+}
+
+fn query_owner_portfolio(
+    deps: Deps,
+    parent: AllianceNftCollection,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OwnerPortfolioResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let owner = deps.api.addr_validate(&owner)?;
+    let exchange_rate = cfg
+        .lst_provider
+        .query_exchange_rate(&deps.querier, &cfg.lst_hub)?;
+
+    let nfts = parent
+        .tokens
+        .idx
+        .owner
+        .prefix(owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|token_id| {
+            let token_id = token_id?;
+            let broken = BROKEN_NFTS
+                .may_load(deps.storage, token_id.clone())?
+                .unwrap_or(false);
+            let (rewards, asset_rewards) = if broken {
+                (Uint128::zero(), vec![])
+            } else {
+                (
+                    query_nft_rewards(deps.storage, token_id.clone())?,
+                    query_nft_asset_rewards(deps.storage, token_id.clone())?,
+                )
+            };
+            Ok(PortfolioNft {
+                token_id,
+                broken,
+                rewards,
+                luna_rewards: rewards.mul_floor(exchange_rate),
+                asset_rewards,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let total_rewards = nfts.iter().map(|nft| nft.rewards).sum::<Uint128>();
+    let mut total_asset_rewards: Vec<Asset> = vec![];
+    for nft in nfts.iter() {
+        add_assets(&mut total_asset_rewards, nft.asset_rewards.clone());
+    }
+    Ok(OwnerPortfolioResponse {
+        nfts,
+        total_rewards,
+        total_luna_rewards: total_rewards.mul_floor(exchange_rate),
+        total_asset_rewards,
+        exchange_rate,
+    })
+}
+
+fn query_simulate_break(
+    deps: Deps,
+    parent: AllianceNftCollection,
+    token_ids: Vec<String>,
+) -> StdResult<SimulateBreakResponse> {
+    if token_ids.is_empty() {
+        return Err(StdError::generic_err(
+            ContractError::EmptyTokenIds {}.to_string(),
+        ));
+    }
+
+    let mut nfts: Vec<SimulatedBreak> = vec![];
+    let mut total_rewards = Uint128::zero();
+    let mut total_asset_rewards: Vec<Asset> = vec![];
+    for token_id in token_ids {
+        if nfts.iter().any(|nft| nft.token_id == token_id) {
+            return Err(StdError::generic_err(
+                ContractError::DuplicateTokenId(token_id).to_string(),
+            ));
+        }
+        // fails for tokens that do not exist
+        parent.tokens.load(deps.storage, &token_id)?;
+        if BROKEN_NFTS
+            .may_load(deps.storage, token_id.clone())?
+            .unwrap_or(false)
+        {
+            return Err(StdError::generic_err(
+                ContractError::AlreadyBroken {}.to_string(),
+            ));
+        }
+
+        let rewards = query_nft_rewards(deps.storage, token_id.clone())?;
+        let asset_rewards = query_nft_asset_rewards(deps.storage, token_id.clone())?;
+        total_rewards += rewards;
+        add_assets(&mut total_asset_rewards, asset_rewards.clone());
+        nfts.push(SimulatedBreak {
+            token_id,
+            rewards,
+            asset_rewards,
+        });
+    }
+
+    Ok(SimulateBreakResponse {
+        nfts,
+        total_rewards,
+        total_asset_rewards,
+    })
+}
+
+// Follows AllianceClaimRewards: the rewards of all validators are claimed and, together with
+// the LUNA held by the contract, bonded for the LST. The LST received is estimated from the
+// current exchange rate of the hub, the hub may round the amount minted differently.
+fn query_simulate_harvest(deps: Deps, env: Env) -> StdResult<SimulateHarvestResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if let Some(last_harvest) = LAST_HARVEST.may_load(deps.storage)? {
+        let next_harvest = last_harvest.plus_seconds(cfg.harvest_interval);
+        if env.block.time < next_harvest {
+            return Err(StdError::generic_err(
+                ContractError::HarvestTooEarly(next_harvest).to_string(),
+            ));
+        }
+    }
+
+    let mut pending_rewards = Uint128::zero();
+    for item in VALS.keys(deps.storage, None, None, Order::Ascending) {
+        let validator = item?;
+        pending_rewards += query_alliance_delegation_rewards(
+            &deps.querier,
+            &env.contract.address,
+            &validator,
+            &cfg.asset_denom,
+        )?
+        .iter()
+        .filter(|coin| coin.denom == ALLOWED_DENOM)
+        .map(|coin| Uint128::from_str(&coin.amount))
+        .sum::<StdResult<Uint128>>()?;
+    }
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, ALLOWED_DENOM)?
+        .amount;
+    let luna_to_bond = pending_rewards + balance;
+
+    let exchange_rate = cfg
+        .lst_provider
+        .query_exchange_rate(&deps.querier, &cfg.lst_hub)?;
+    let expected_lst = luna_to_bond
+        .checked_multiply_ratio(Decimal::one().atomics(), exchange_rate.atomics())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let treasury_amount = cfg.dao_treasury_share * expected_lst;
+    let keeper_fee = cfg.keeper_fee * expected_lst;
+    let rewards = expected_lst
+        .checked_sub(treasury_amount)?
+        .checked_sub(keeper_fee)?;
+    let index_increment = REWARD_INDEX
+        .load(deps.storage)?
+        .distribute(rewards, ACTIVE_WEIGHT.load(deps.storage)?)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(SimulateHarvestResponse {
+        pending_rewards,
+        luna_to_bond,
+        exchange_rate,
+        expected_lst,
+        treasury_amount,
+        keeper_fee,
+        index_increment,
+    })
+}
+
+fn query_validators(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ValidatorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let validators = VALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(validator, stake)| ValidatorStake { validator, stake }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ValidatorsResponse { validators })
+}
+
+fn query_delegation_drift(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<DelegationDriftResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let validators = VALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (validator, tracked) = item?;
+            let actual = query_alliance_delegation(
+                &deps.querier,
+                &env.contract.address,
+                &validator,
+                &cfg.asset_denom,
+            )?;
+            Ok(ValidatorDrift {
+                validator,
+                tracked,
+                actual,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(DelegationDriftResponse { validators })
+}
+
+fn query_harvest_history(
+    deps: Deps,
+    start_before: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<HarvestHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_before.map(Bound::exclusive);
+
+    let harvests = HARVESTS
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(HarvestHistoryResponse { harvests })
+}
+
+fn query_reward_rate(deps: Deps, env: Env, window_seconds: u64) -> StdResult<RewardRateResponse> {
+    if window_seconds == 0 {
+        return Err(StdError::generic_err("window_seconds must be positive"));
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    let window_start = env.block.time.seconds().saturating_sub(window_seconds);
+
+    // harvests before the last LST switch were paid in another LST
+    let min = LST_SWITCH_HARVEST_ID
+        .may_load(deps.storage)?
+        .map(Bound::inclusive);
+    let mut harvests = 0u64;
+    let mut lst_per_weight = Decimal256::zero();
+    for item in HARVESTS.range(deps.storage, min, None, Order::Descending) {
+        let (_, harvest) = item?;
+        if harvest.time.seconds() <= window_start {
+            break;
+        }
+        harvests += 1;
+        lst_per_weight = lst_per_weight.checked_add(harvest.index_increment)?;
+    }
+
+    let exchange_rate = Decimal256::from(
+        cfg.lst_provider
+            .query_exchange_rate(&deps.querier, &cfg.lst_hub)?,
+    );
+    let luna_per_weight = lst_per_weight.checked_mul(exchange_rate)?;
+    let annualise = Decimal256::from_ratio(SECONDS_PER_YEAR, window_seconds);
+
+    Ok(RewardRateResponse {
+        window_seconds,
+        harvests,
+        lst_per_weight,
+        luna_per_weight,
+        annual_lst_per_weight: lst_per_weight.checked_mul(annualise)?,
+        annual_luna_per_weight: luna_per_weight.checked_mul(annualise)?,
+    })
+}
+
+fn query_unbondings(deps: Deps, env: Env) -> StdResult<UnbondingsResponse> {
+    // matured unbondings are only removed on the next undelegation or harvest
+    let unbondings = UNBONDINGS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, unbonding)) => unbonding.completion_time > env.block.time,
+            Err(_) => true,
+        })
+        .map(|item| item.map(|(_, unbonding)| unbonding))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(UnbondingsResponse { unbondings })
+}
+
+fn query_unbond_requests(
+    deps: Deps,
+    user: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<UnbondRequestsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let user = deps.api.addr_validate(&user)?;
+    let requests = cfg.lst_provider.query_unbond_requests(
+        &deps.querier,
+        &cfg.lst_hub,
+        user.to_string(),
+        start_after,
+        limit,
+    )?;
+    Ok(UnbondRequestsResponse { requests })
+}
+
+fn query_reward_state(deps: Deps) -> StdResult<RewardStateResponse> {
+    let asset_rewards = ASSET_REWARD_INDEXES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (asset_info, reward_index) = item?;
+            let held = ASSET_REWARDS_HELD
+                .may_load(deps.storage, &asset_info)?
+                .unwrap_or_default();
+            Ok(AssetRewardState {
+                asset_info,
+                reward_index,
+                held,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RewardStateResponse {
+        reward_index: REWARD_INDEX.load(deps.storage)?,
+        num_active_nfts: NUM_ACTIVE_NFTS.load(deps.storage)?,
+        total_active_weight: ACTIVE_WEIGHT.load(deps.storage)?,
+        asset_rewards,
+    })
+}
+
+fn query_broken_nfts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BrokenNftsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let tokens = BROKEN_NFTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, false))))
+        .take(limit)
+        .map(|item| item.map(|(token_id, _)| token_id))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(BrokenNftsResponse { tokens })
+}
+
 fn query_token_info(
     deps: Deps,
     parent: AllianceNftCollection,
@@ -49,11 +493,7 @@ fn query_token_info(
     let rewards_left = if is_broken {
         Uint128::zero()
     } else {
-        let reward_balance = REWARD_BALANCE.load(deps.storage)?;
-        let claimed_reward = NFT_BALANCE_CLAIMED.load(deps.storage, token_id)?;
-        reward_balance
-            .checked_sub(claimed_reward)
-            .unwrap_or(Uint128::zero())
+        query_nft_rewards(deps.storage, token_id)?
     };
 
     let mut traits = info.extension.attributes.unwrap();
@@ -128,3 +568,100 @@ pub fn try_query_contract_balance(
     let contract_balance = querier.query_balance(contract_addr, ALLOWED_DENOM)?.amount;
     Ok(contract_balance)
 }
+
+// Query the rewards of the contract pending for its delegation to the validator in the
+// Alliance module. The module returns an error when there is no delegation, read as no rewards.
+// Any other error is returned, so that a failing query is never read as no rewards.
+pub fn query_alliance_delegation_rewards(
+    querier: &QuerierWrapper,
+    delegator: &Addr,
+    validator: &str,
+    denom: &str,
+) -> StdResult<Vec<Coin>> {
+    let request: QueryRequest<Empty> = QueryRequest::Stargate {
+        path: "/alliance.alliance.Query/AllianceDelegationRewards".to_string(),
+        data: Binary::from(
+            QueryAllianceDelegationRewardsRequest {
+                delegator_addr: delegator.to_string(),
+                validator_addr: validator.to_string(),
+                denom: denom.to_string(),
+                pagination: None,
+            }
+            .encode_to_vec(),
+        ),
+    };
+
+    let res = match querier.raw_query(&to_json_vec(&request)?) {
+        SystemResult::Err(system_err) => {
+            return Err(StdError::generic_err(format!(
+                "Querier system error: {system_err}"
+            )))
+        }
+        SystemResult::Ok(ContractResult::Err(err)) => {
+            if is_delegation_not_found(&err) {
+                return Ok(vec![]);
+            }
+            return Err(StdError::generic_err(format!(
+                "Alliance delegation rewards query failed: {err}"
+            )));
+        }
+        SystemResult::Ok(ContractResult::Ok(res)) => res,
+    };
+
+    QueryAllianceDelegationRewardsResponse::decode(res.as_slice())
+        .map(|res| res.rewards)
+        .map_err(|err| StdError::parse_err("QueryAllianceDelegationRewardsResponse", err))
+}
+
+// Query the balance delegated by the contract to the validator in the Alliance module.
+// The module returns an error when there is no delegation, which is read as no stake.
+// Any other error is returned, so that a failing query is never read as a lost delegation.
+pub fn query_alliance_delegation(
+    querier: &QuerierWrapper,
+    delegator: &Addr,
+    validator: &str,
+    denom: &str,
+) -> StdResult<Uint128> {
+    let request: QueryRequest<Empty> = QueryRequest::Stargate {
+        path: "/alliance.alliance.Query/AllianceDelegation".to_string(),
+        data: Binary::from(
+            QueryAllianceDelegationRequest {
+                delegator_addr: delegator.to_string(),
+                validator_addr: validator.to_string(),
+                denom: denom.to_string(),
+                pagination: None,
+            }
+            .encode_to_vec(),
+        ),
+    };
+
+    let res = match querier.raw_query(&to_json_vec(&request)?) {
+        SystemResult::Err(system_err) => {
+            return Err(StdError::generic_err(format!(
+                "Querier system error: {system_err}"
+            )))
+        }
+        SystemResult::Ok(ContractResult::Err(err)) => {
+            if is_delegation_not_found(&err) {
+                return Ok(Uint128::zero());
+            }
+            return Err(StdError::generic_err(format!(
+                "Alliance delegation query failed: {err}"
+            )));
+        }
+        SystemResult::Ok(ContractResult::Ok(res)) => res,
+    };
+
+    let balance = QueryAllianceDelegationResponse::decode(res.as_slice())
+        .map_err(|err| StdError::parse_err("QueryAllianceDelegationResponse", err))?
+        .delegation
+        .and_then(|delegation| delegation.balance);
+    match balance {
+        Some(coin) => Uint128::from_str(&coin.amount),
+        None => Ok(Uint128::zero()),
+    }
+}
+
+fn is_delegation_not_found(err: &str) -> bool {
+    err.to_lowercase().contains("delegation not found")
+}
@@ -1,10 +1,13 @@
 use cosmwasm_std::{entry_point, DepsMut, Env, Reply, Response};
 
+use super::execute::{reply_on_bond, reply_on_switch_lst};
 use super::instantiate::reply_on_instantiate;
 use alliance_nft_packages::errors::ContractError;
 
 pub const INSTANTIATE_REPLY_ID: u64 = 1;
 pub const CLAIM_REWARD_ERROR_REPLY_ID: u64 = 2;
+pub const BOND_REPLY_ID: u64 = 3;
+pub const SWITCH_LST_REPLY_ID: u64 = 4;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
@@ -14,6 +17,8 @@ pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, Contract
             "claim_reward_error_reply",
             CLAIM_REWARD_ERROR_REPLY_ID.to_string(),
         )),
+        BOND_REPLY_ID => reply_on_bond(deps, env, reply),
+        SWITCH_LST_REPLY_ID => reply_on_switch_lst(deps, env),
         _ => Err(ContractError::InvalidReplyId(reply.id)),
     }
 }
@@ -1,13 +1,66 @@
-use cosmwasm_std::{StdError, Storage, Uint128};
+use cosmwasm_std::{Addr, Decimal256, Order, StdError, StdResult, Storage, Timestamp, Uint128};
+use cw_asset::{Asset, AssetInfo};
 use cw_storage_plus::{Item, Map};
 
-use alliance_nft_packages::{errors::ContractError, state::Config};
+use alliance_nft_packages::{
+    eris::AssetInfoExt,
+    errors::ContractError,
+    state::{Config, Harvest, LstSwitch, PendingHarvest, RewardIndex, Unbonding},
+    Extension,
+};
 
 pub const CONFIG: Item<Config> = Item::new("cfg");
 
+// Time of the last harvest, used to enforce the harvest interval
+pub const LAST_HARVEST: Item<Timestamp> = Item::new("lh");
+
+// Harvest in progress while the rewards are bonded, removed in the bond reply
+pub const PENDING_HARVEST: Item<PendingHarvest> = Item::new("ph");
+
+// Swap of the held LST in progress, removed in the swap reply
+pub const LST_SWITCH: Item<LstSwitch> = Item::new("ls");
+
+// Log of the harvests by sequence number
+pub const HARVESTS: Map<u64, Harvest> = Map::new("hv");
+pub const HARVEST_ID: Item<u64> = Item::new("hvid");
+// First harvest paid in the current LST, earlier harvests are not comparable anymore
+pub const LST_SWITCH_HARVEST_ID: Item<u64> = Item::new("lshid");
+
+pub fn add_harvest(storage: &mut dyn Storage, harvest: &Harvest) -> StdResult<()> {
+    let id = HARVEST_ID.may_load(storage)?.unwrap_or_default();
+    HARVESTS.save(storage, id, harvest)?;
+    HARVEST_ID.save(storage, &(id + 1))
+}
+
 // Keep track of validators and stake
 pub const VALS: Map<String, Uint128> = Map::new("val");
 
+// Keep track of the undelegations until they complete
+pub const UNBONDINGS: Map<u64, Unbonding> = Map::new("unb");
+pub const UNBONDING_ID: Item<u64> = Item::new("unbid");
+
+pub fn add_unbonding(storage: &mut dyn Storage, unbonding: &Unbonding) -> StdResult<()> {
+    let id = UNBONDING_ID.may_load(storage)?.unwrap_or_default();
+    UNBONDINGS.save(storage, id, unbonding)?;
+    UNBONDING_ID.save(storage, &(id + 1))
+}
+
+// Removes the undelegations whose tokens were returned to the contract
+pub fn remove_matured_unbondings(storage: &mut dyn Storage, now: Timestamp) -> StdResult<()> {
+    let matured = UNBONDINGS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, unbonding)) => unbonding.completion_time <= now,
+            Err(_) => true,
+        })
+        .map(|item| item.map(|(id, _)| id))
+        .collect::<StdResult<Vec<u64>>>()?;
+    for id in matured {
+        UNBONDINGS.remove(storage, id);
+    }
+    Ok(())
+}
+
 pub fn upsert_val(
     storage: &mut dyn Storage,
     validator: String,
@@ -38,8 +91,205 @@ pub fn reduce_val_stake(
     Ok(())
 }
 
-// Keep track of rewards claimed for each token_id
-pub const NFT_BALANCE_CLAIMED: Map<String, Uint128> = Map::new("nb");
-pub const REWARD_BALANCE: Item<Uint128> = Item::new("rb");
+// Keep track of the reward index when rewards were last claimed for each token_id.
+// Checkpoints stored as Uint128 by previous versions are read as a Decimal256 of the same value.
+pub const NFT_BALANCE_CLAIMED: Map<String, Decimal256> = Map::new("nb");
+pub const REWARD_INDEX: Item<RewardIndex> = Item::new("ri");
 pub const NUM_ACTIVE_NFTS: Item<u64> = Item::new("nan");
 pub const BROKEN_NFTS: Map<String, bool> = Map::new("bn");
+
+// Reward weight of each token_id, tokens minted before weights were introduced have a weight of 1
+pub const NFT_WEIGHTS: Map<String, u64> = Map::new("nw");
+// Token ids whose weight was set explicitly at mint, these are not derived from the weight trait
+pub const FIXED_NFT_WEIGHTS: Map<String, bool> = Map::new("fnw");
+// Sum of the weights of the active NFTs, the rewards are split by this total
+pub const ACTIVE_WEIGHT: Item<u64> = Item::new("aw");
+
+// Address receiving the rewards of a token_id instead of its owner, cleared on transfer
+pub const REWARD_RECIPIENTS: Map<String, Addr> = Map::new("rr");
+
+// Metadata of the NFTs that can be forged by token_id, each entry is minted once
+pub const FORGE_METADATA: Map<String, Extension> = Map::new("fm");
+
+pub fn reward_recipient(storage: &dyn Storage, token_id: String, owner: Addr) -> StdResult<Addr> {
+    Ok(REWARD_RECIPIENTS
+        .may_load(storage, token_id)?
+        .unwrap_or(owner))
+}
+
+pub fn nft_weight(storage: &dyn Storage, token_id: String) -> StdResult<u64> {
+    Ok(NFT_WEIGHTS.may_load(storage, token_id)?.unwrap_or(1))
+}
+
+// Moves the checkpoint of the token to the current reward index
+// and returns the rewards accrued since the previous checkpoint
+pub fn checkpoint_nft_rewards(
+    storage: &mut dyn Storage,
+    token_id: String,
+) -> Result<Uint128, ContractError> {
+    let weight = nft_weight(storage, token_id.clone())?;
+    let checkpoint = NFT_BALANCE_CLAIMED.load(storage, token_id.clone())?;
+    let (rewards_claimable, checkpoint) = REWARD_INDEX.load(storage)?.claim(checkpoint, weight)?;
+
+    NFT_BALANCE_CLAIMED.save(storage, token_id, &checkpoint)?;
+    Ok(rewards_claimable)
+}
+
+// Returns the rewards accrued by the token since its checkpoint
+pub fn query_nft_rewards(storage: &dyn Storage, token_id: String) -> StdResult<Uint128> {
+    let weight = nft_weight(storage, token_id.clone())?;
+    let checkpoint = NFT_BALANCE_CLAIMED.load(storage, token_id)?;
+    REWARD_INDEX.load(storage)?.pending(checkpoint, weight)
+}
+
+// Keep track of rewards per NFT for the reward assets other than the LST
+// (e.g. take rate rewards of an Alliance paid out in other denoms)
+pub const ASSET_REWARD_INDEXES: Map<&AssetInfo, RewardIndex> = Map::new("ari");
+pub const NFT_ASSET_BALANCE_CLAIMED: Map<(String, &AssetInfo), Decimal256> = Map::new("nab");
+// Amount of each reward asset held by the contract that is already accounted to the NFTs
+pub const ASSET_REWARDS_HELD: Map<&AssetInfo, Uint128> = Map::new("arh");
+
+// Moves the checkpoints of the token to the current reward index of every
+// additional reward asset and returns the rewards accrued since the previous checkpoints
+pub fn checkpoint_nft_asset_rewards(
+    storage: &mut dyn Storage,
+    token_id: String,
+) -> Result<Vec<Asset>, ContractError> {
+    let indexes = ASSET_REWARD_INDEXES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(AssetInfo, RewardIndex)>>>()?;
+    let weight = nft_weight(storage, token_id.clone())?;
+
+    let mut rewards = vec![];
+    for (asset_info, reward_index) in indexes {
+        // tokens minted before the asset was added did not claim anything yet
+        let checkpoint = NFT_ASSET_BALANCE_CLAIMED
+            .may_load(storage, (token_id.clone(), &asset_info))?
+            .unwrap_or_default();
+        let (rewards_claimable, checkpoint) = reward_index.claim(checkpoint, weight)?;
+        NFT_ASSET_BALANCE_CLAIMED.save(storage, (token_id.clone(), &asset_info), &checkpoint)?;
+
+        if !rewards_claimable.is_zero() {
+            ASSET_REWARDS_HELD.update(storage, &asset_info, |held| -> StdResult<_> {
+                Ok(held.unwrap_or_default().checked_sub(rewards_claimable)?)
+            })?;
+            rewards.push(asset_info.with_balance(rewards_claimable));
+        }
+    }
+    Ok(rewards)
+}
+
+// Returns the rewards in the additional reward assets accrued by the token since its checkpoints
+pub fn query_nft_asset_rewards(storage: &dyn Storage, token_id: String) -> StdResult<Vec<Asset>> {
+    let weight = nft_weight(storage, token_id.clone())?;
+    let mut rewards = vec![];
+    for item in ASSET_REWARD_INDEXES.range(storage, None, None, Order::Ascending) {
+        let (asset_info, reward_index) = item?;
+        let checkpoint = NFT_ASSET_BALANCE_CLAIMED
+            .may_load(storage, (token_id.clone(), &asset_info))?
+            .unwrap_or_default();
+        let amount = reward_index.pending(checkpoint, weight)?;
+        if !amount.is_zero() {
+            rewards.push(asset_info.with_balance(amount));
+        }
+    }
+    Ok(rewards)
+}
+
+// Sums up the amounts of the assets with the same asset info
+pub fn add_assets(total: &mut Vec<Asset>, assets: Vec<Asset>) {
+    for asset in assets {
+        match total.iter_mut().find(|a| a.info == asset.info) {
+            Some(existing) => existing.amount += asset.amount,
+            None => total.push(asset),
+        }
+    }
+}
+
+// Changes the reward weight of the token, moving its checkpoints so that the rewards
+// accrued with the previous weight stay pending. Rewards that the checkpoint cannot hold
+// with the new weight are claimed instead and returned to be paid out.
+pub fn reweight_nft(
+    storage: &mut dyn Storage,
+    token_id: String,
+    weight: u64,
+) -> Result<(Uint128, Vec<Asset>), ContractError> {
+    let old_weight = nft_weight(storage, token_id.clone())?;
+
+    let reward_index = REWARD_INDEX.load(storage)?;
+    let checkpoint = NFT_BALANCE_CLAIMED.load(storage, token_id.clone())?;
+    let (rewards, checkpoint) = match reward_index.reweight(checkpoint, old_weight, weight)? {
+        Some(checkpoint) => (Uint128::zero(), checkpoint),
+        None => (
+            reward_index.pending(checkpoint, old_weight)?,
+            reward_index.index,
+        ),
+    };
+    NFT_BALANCE_CLAIMED.save(storage, token_id.clone(), &checkpoint)?;
+
+    let indexes = ASSET_REWARD_INDEXES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(AssetInfo, RewardIndex)>>>()?;
+    let mut asset_rewards = vec![];
+    for (asset_info, reward_index) in indexes {
+        let checkpoint = NFT_ASSET_BALANCE_CLAIMED
+            .may_load(storage, (token_id.clone(), &asset_info))?
+            .unwrap_or_default();
+        let checkpoint = match reward_index.reweight(checkpoint, old_weight, weight)? {
+            Some(checkpoint) => checkpoint,
+            None => {
+                let amount = reward_index.pending(checkpoint, old_weight)?;
+                if !amount.is_zero() {
+                    ASSET_REWARDS_HELD.update(storage, &asset_info, |held| -> StdResult<_> {
+                        Ok(held.unwrap_or_default().checked_sub(amount)?)
+                    })?;
+                    asset_rewards.push(asset_info.clone().with_balance(amount));
+                }
+                reward_index.index
+            }
+        };
+        NFT_ASSET_BALANCE_CLAIMED.save(storage, (token_id.clone(), &asset_info), &checkpoint)?;
+    }
+
+    NFT_WEIGHTS.save(storage, token_id, &weight)?;
+    ACTIVE_WEIGHT.update(storage, |w| -> StdResult<_> { Ok(w - old_weight + weight) })?;
+    Ok((rewards, asset_rewards))
+}
+
+// Adds the token to the active NFTs, moving its checkpoints to the current reward
+// indexes so that it only accrues the rewards distributed from now on
+pub fn activate_nft(storage: &mut dyn Storage, token_id: String) -> StdResult<()> {
+    let weight = nft_weight(storage, token_id.clone())?;
+    NUM_ACTIVE_NFTS.update(storage, |n| -> StdResult<_> { Ok(n + 1) })?;
+    ACTIVE_WEIGHT.update(storage, |w| -> StdResult<_> { Ok(w + weight) })?;
+
+    let reward_index = REWARD_INDEX.load(storage)?;
+    NFT_BALANCE_CLAIMED.save(storage, token_id.clone(), &reward_index.index)?;
+    let asset_reward_indexes = ASSET_REWARD_INDEXES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(AssetInfo, RewardIndex)>>>()?;
+    for (asset_info, reward_index) in asset_reward_indexes {
+        NFT_ASSET_BALANCE_CLAIMED.save(
+            storage,
+            (token_id.clone(), &asset_info),
+            &reward_index.index,
+        )?;
+    }
+    Ok(())
+}
+
+// Removes the reward checkpoints and settings of a token_id that no longer exists
+pub fn remove_nft(storage: &mut dyn Storage, token_id: String) -> StdResult<()> {
+    let asset_infos = ASSET_REWARD_INDEXES
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<AssetInfo>>>()?;
+    for asset_info in asset_infos {
+        NFT_ASSET_BALANCE_CLAIMED.remove(storage, (token_id.clone(), &asset_info));
+    }
+    NFT_BALANCE_CLAIMED.remove(storage, token_id.clone());
+    NFT_WEIGHTS.remove(storage, token_id.clone());
+    FIXED_NFT_WEIGHTS.remove(storage, token_id.clone());
+    BROKEN_NFTS.remove(storage, token_id.clone());
+    REWARD_RECIPIENTS.remove(storage, token_id);
+    Ok(())
+}
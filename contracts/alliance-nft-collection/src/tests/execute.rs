@@ -1,11 +1,45 @@
-use crate::tests::helpers::{break_nft, claim_alliance_emissions, mint, query_nft, setup_contract};
-use alliance_nft_packages::Extension;
-use alliance_nft_packages::execute::{ExecuteCollectionMsg, MintMsg};
-use alliance_nft_packages::state::Trait;
-use cosmwasm_std::testing::{mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info};
-use cosmwasm_std::{BankMsg, Coin, CosmosMsg, Response, Uint128};
-use cw721::NftInfoResponse;
 use crate::contract::execute::execute;
+use crate::contract::query::query;
+use crate::contract::reply::{reply, BOND_REPLY_ID, SWITCH_LST_REPLY_ID};
+use crate::state::{
+    BROKEN_NFTS, CONFIG, FORGE_METADATA, HARVESTS, LST_SWITCH, LST_SWITCH_HARVEST_ID,
+    NFT_BALANCE_CLAIMED, NFT_WEIGHTS, PENDING_HARVEST, UNBONDINGS, VALS,
+};
+use crate::tests::helpers::{
+    bond_reply, break_nft, claim_alliance_emissions, mint, mint_with_weight, query_nft,
+    set_contract_balance, setup_contract, setup_contract_with_provider, ASSET_DENOM, LST_DENOM,
+};
+use crate::tests::mock_querier::mock_dependencies_with_alliance;
+use alliance_nft_packages::eris::{self, Hub};
+use alliance_nft_packages::errors::ContractError;
+use alliance_nft_packages::execute::{
+    AllianceDelegateMsg, AllianceDelegation, AllianceUndelegateMsg, BreakPayout,
+    ExecuteCollectionMsg, MintMsg, SwitchLstMsg, UpdateConfigMsg,
+};
+use alliance_nft_packages::lst::{steak, LstProvider};
+use alliance_nft_packages::query::{
+    NftWeightResponse, QueryCollectionMsg, RewardRecipientResponse, RewardStateResponse,
+    UnbondingsResponse,
+};
+use alliance_nft_packages::state::{
+    Config, LstSwitch, MinterExtension, PendingHarvest, Trait, Unbonding,
+};
+use alliance_nft_packages::Extension;
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier,
+    MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+    Env, Event, Order, OwnedDeps, Reply, Response, SubMsg, SubMsgResponse, SubMsgResult, Uint128,
+    WasmMsg,
+};
+use cw721::{NftInfoResponse, NumTokensResponse};
+use cw_asset::{Asset, AssetInfo, AssetInfoUnchecked, AssetUnchecked};
+use std::collections::BTreeMap;
+use terra_proto_rs::alliance::alliance::{MsgDelegate, MsgRedelegate};
+use terra_proto_rs::cosmos::base::v1beta1::Coin as ProtoCoin;
+use terra_proto_rs::traits::Message;
 
 #[test]
 fn mint_and_query_nft() {
@@ -67,48 +101,62 @@ fn mint_invalid() {
     mint(deps.as_mut(), "1");
 
     // Mint with the same token id
-    execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), ExecuteCollectionMsg::Mint(MintMsg {
-        owner: "owner".to_string(),
-        token_id: "1".to_string(),
-        token_uri: None,
-        extension: Extension {
-            image: Some("image".to_string()),
-            image_data: None,
-            external_url: None,
-            description: None,
-            name: None,
-            attributes: Some(vec![Trait {
-                display_type: None,
-                trait_type: "trait_type".to_string(),
-                value: "value".to_string(),
-            }]),
-            background_color: None,
-            animation_url: None,
-            youtube_url: None,
-        },
-    })).unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        ExecuteCollectionMsg::Mint(MintMsg {
+            owner: "owner".to_string(),
+            token_id: "1".to_string(),
+            token_uri: None,
+            weight: None,
+            extension: Extension {
+                image: Some("image".to_string()),
+                image_data: None,
+                external_url: None,
+                description: None,
+                name: None,
+                attributes: Some(vec![Trait {
+                    display_type: None,
+                    trait_type: "trait_type".to_string(),
+                    value: "value".to_string(),
+                }]),
+                background_color: None,
+                animation_url: None,
+                youtube_url: None,
+            },
+        }),
+    )
+    .unwrap_err();
 
     // Mint with the wrong minter
-    execute(deps.as_mut(), mock_env(), mock_info("wrong_minter", &[]), ExecuteCollectionMsg::Mint(MintMsg {
-        owner: "owner".to_string(),
-        token_id: "2".to_string(),
-        token_uri: None,
-        extension: Extension {
-            image: Some("image".to_string()),
-            image_data: None,
-            external_url: None,
-            description: None,
-            name: None,
-            attributes: Some(vec![Trait {
-                display_type: None,
-                trait_type: "trait_type".to_string(),
-                value: "value".to_string(),
-            }]),
-            background_color: None,
-            animation_url: None,
-            youtube_url: None,
-        },
-    })).unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("wrong_minter", &[]),
+        ExecuteCollectionMsg::Mint(MintMsg {
+            owner: "owner".to_string(),
+            token_id: "2".to_string(),
+            token_uri: None,
+            weight: None,
+            extension: Extension {
+                image: Some("image".to_string()),
+                image_data: None,
+                external_url: None,
+                description: None,
+                name: None,
+                attributes: Some(vec![Trait {
+                    display_type: None,
+                    trait_type: "trait_type".to_string(),
+                    value: "value".to_string(),
+                }]),
+                background_color: None,
+                animation_url: None,
+                youtube_url: None,
+            },
+        }),
+    )
+    .unwrap_err();
 }
 
 #[test]
@@ -166,11 +214,11 @@ fn break_nft_without_rewards() {
 
 #[test]
 fn break_nft_with_rewards() {
-    let mut deps = mock_dependencies_with_balance(&[Coin::new(1_000_000_000, "uluna")]);
+    let mut deps = mock_dependencies();
     setup_contract(deps.as_mut());
     mint(deps.as_mut(), "1");
     mint(deps.as_mut(), "2");
-    claim_alliance_emissions(deps.as_mut(), Uint128::new(500_000_000));
+    claim_alliance_emissions(&mut deps, Uint128::new(500_000_000));
 
     let nft = query_nft(deps.as_ref(), "1");
     assert_eq!(
@@ -212,7 +260,7 @@ fn break_nft_with_rewards() {
         res,
         Response::default()
             .add_message(CosmosMsg::Bank(BankMsg::Send {
-                amount: vec![Coin::new(250_000_000, "uluna")],
+                amount: vec![Coin::new(250_000_000, LST_DENOM)],
                 to_address: "owner".to_string(),
             }))
             .add_attributes(vec![
@@ -258,7 +306,7 @@ fn break_nft_with_rewards() {
     );
 
     // Claim more rewards from alliance module. All rewards should go to remaining NFTs
-    claim_alliance_emissions(deps.as_mut(), Uint128::new(500_000_000));
+    claim_alliance_emissions(&mut deps, Uint128::new(500_000_000));
     let nft = query_nft(deps.as_ref(), "1");
     assert_eq!(
         nft,
@@ -337,9 +385,2239 @@ fn break_nft_invalid() {
     mint(deps.as_mut(), "1");
 
     // Cannot break as a different owner
-    execute(deps.as_mut(), mock_env(), mock_info("owner2", &[]), ExecuteCollectionMsg::BreakNft("1".to_string())).unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        ExecuteCollectionMsg::BreakNft {
+            token_id: "1".to_string(),
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap_err();
     break_nft(deps.as_mut(), "1");
 
     // Cannot break a broken NFT
-    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), ExecuteCollectionMsg::BreakNft("1".to_string())).unwrap_err();
-}
\ No newline at end of file
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::BreakNft {
+            token_id: "1".to_string(),
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap_err();
+}
+#[test]
+fn claim_rewards_keeps_nft_active() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(500_000_000));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "claim_rewards"),
+                ("token_id", "1"),
+                ("rewards", "250000000"),
+            ])
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(250_000_000, LST_DENOM)],
+                to_address: "owner".to_string(),
+            }))
+            .add_attribute("total_rewards", "250000000")
+    );
+
+    // the NFT is not broken and keeps accruing rewards
+    claim_alliance_emissions(&mut deps, Uint128::new(500_000_000));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[1].value, "false");
+    assert_eq!(attributes[2].value, "250000000");
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "500000000");
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(750_000_000, LST_DENOM)],
+            to_address: "owner".to_string(),
+        })
+    );
+
+    // nothing left to claim
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn claim_rewards_invalid() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+
+    // Cannot claim without token ids
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards { token_ids: vec![] },
+    )
+    .unwrap_err();
+
+    // Cannot claim as a different owner
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string()],
+        },
+    )
+    .unwrap_err();
+
+    // Cannot claim for a broken NFT
+    break_nft(deps.as_mut(), "2");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn break_nfts_in_batch() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    mint(deps.as_mut(), "3");
+    claim_alliance_emissions(&mut deps, Uint128::new(300_000_000));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::BreakNfts {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "break_nfts"),
+                ("token_id", "1"),
+                ("rewards", "100000000"),
+                ("token_id", "2"),
+                ("rewards", "100000000"),
+                ("total_rewards", "200000000"),
+            ])
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(200_000_000, LST_DENOM)],
+                to_address: "owner".to_string(),
+            }))
+    );
+
+    // the broken NFTs stop accruing rewards
+    claim_alliance_emissions(&mut deps, Uint128::new(300_000_000));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[1].value, "true");
+    assert_eq!(attributes[2].value, "0");
+    let attributes = query_nft(deps.as_ref(), "3").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "400000000");
+}
+
+#[test]
+fn break_nfts_invalid() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+
+    // Cannot break without token ids
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::BreakNfts {
+            token_ids: vec![],
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap_err();
+
+    // Cannot break as a different owner
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        ExecuteCollectionMsg::BreakNfts {
+            token_ids: vec!["1".to_string()],
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap_err();
+
+    // Cannot break the same NFT twice
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::BreakNfts {
+            token_ids: vec!["1".to_string(), "1".to_string()],
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap_err();
+
+    break_nft(deps.as_mut(), "2");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::BreakNfts {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+            payout: BreakPayout::Lst,
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn asset_rewards_distributed_per_nft() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+            dao_treasury_share: Some(Decimal::percent(10)),
+            reward_assets: Some(vec![AssetInfoUnchecked::native("ustars")]),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+
+    // the take rate rewards paid in ustars are accounted without staking them
+    set_contract_balance(&mut deps, Coin::new(1_001, "ustars"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteCollectionMsg::StakeRewardsCallback { keeper: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(100, "ustars")],
+            to_address: "dao_treasury".to_string(),
+        })
+    );
+
+    let res = break_nft(deps.as_mut(), "1");
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "break_nft"),
+                ("token_id", "1"),
+                ("rewards", "0"),
+                ("asset_rewards", "native:ustars:450"),
+            ])
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(450, "ustars")],
+                to_address: "owner".to_string(),
+            }))
+    );
+
+    // a newly minted NFT does not receive the rewards distributed before it existed
+    mint(deps.as_mut(), "3");
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["2".to_string(), "3".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(450, "ustars")],
+            to_address: "owner".to_string(),
+        }))]
+    );
+}
+
+#[test]
+fn asset_rewards_exclude_balance_held_before_listing() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+
+    // GIVEN the contract holds ustars before they are listed as reward asset
+    set_contract_balance(&mut deps, Coin::new(500, "ustars"));
+    let update_config = ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+        reward_assets: Some(vec![AssetInfoUnchecked::native("ustars")]),
+        ..Default::default()
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    // WHEN 100 ustars are received and the rewards are harvested
+    set_contract_balance(&mut deps, Coin::new(600, "ustars"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteCollectionMsg::StakeRewardsCallback { keeper: None },
+    )
+    .unwrap();
+
+    // EXPECT only the ustars received after the listing to be distributed
+    let res = break_nft(deps.as_mut(), "1");
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(50, "ustars")],
+            to_address: "owner".to_string(),
+        }))]
+    );
+}
+
+#[test]
+fn update_config_invalid_reward_assets() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    for asset in [
+        AssetInfoUnchecked::native(LST_DENOM),
+        AssetInfoUnchecked::native("uluna"),
+        AssetInfoUnchecked::native("factory/cosmos2contract/AllianceNFT"),
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+                reward_assets: Some(vec![asset]),
+                ..Default::default()
+            }),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRewardAsset(_)));
+    }
+}
+
+#[test]
+fn rewards_remainder_is_carried_over() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    mint(deps.as_mut(), "3");
+
+    // 100 cannot be split evenly between 3 NFTs
+    claim_alliance_emissions(&mut deps, Uint128::new(100));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "33");
+
+    let res = break_nft(deps.as_mut(), "1");
+    assert_eq!(res.attributes[2].value, "33");
+
+    // the fractions left over by the first harvest are paid out with the next one
+    claim_alliance_emissions(&mut deps, Uint128::new(2));
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "34");
+    let attributes = query_nft(deps.as_ref(), "3").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "34");
+}
+
+#[test]
+fn undistributed_rewards_go_to_next_active_nfts() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    // harvesting without active NFTs keeps the rewards undistributed
+    claim_alliance_emissions(&mut deps, Uint128::new(100));
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "0");
+
+    // the next harvest distributes them to the NFTs that are active by then
+    claim_alliance_emissions(&mut deps, Uint128::new(10));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "55");
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "55");
+}
+
+#[test]
+fn sweep_undistributed_rewards() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    break_nft(deps.as_mut(), "1");
+    claim_alliance_emissions(&mut deps, Uint128::new(100));
+
+    // only the owner can sweep
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        ExecuteCollectionMsg::SweepUndistributedRewards {},
+    )
+    .unwrap_err();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::SweepUndistributedRewards {},
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "sweep_undistributed_rewards"),
+                ("swept", "native:ampluna:100"),
+            ])
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(100, LST_DENOM)],
+                to_address: "dao_treasury".to_string(),
+            }))
+    );
+
+    // the swept rewards are not distributed anymore
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::zero());
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "0");
+}
+
+#[test]
+fn rewards_shared_by_weight() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    set_weight_trait(&mut deps, "rarity");
+
+    // the weight is read from the trait, set explicitly or defaults to 1
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("3")], None).unwrap();
+    mint_with_weight(deps.as_mut(), "2", vec![rarity("3")], Some(4)).unwrap();
+    mint(deps.as_mut(), "3");
+    assert_eq!(
+        query_weight(deps.as_ref(), "1"),
+        NftWeightResponse {
+            weight: 3,
+            total_active_weight: 8,
+        }
+    );
+    assert_eq!(query_weight(deps.as_ref(), "2").weight, 4);
+    assert_eq!(query_weight(deps.as_ref(), "3").weight, 1);
+
+    claim_alliance_emissions(&mut deps, Uint128::new(800));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "300");
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "400");
+    let attributes = query_nft(deps.as_ref(), "3").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "100");
+
+    // breaking removes the weight of the NFT from the active weight
+    let res = break_nft(deps.as_mut(), "2");
+    assert_eq!(res.attributes[2].value, "400");
+    assert_eq!(query_weight(deps.as_ref(), "1").total_active_weight, 4);
+
+    // fractions are kept per unit of weight until they add up to a whole unit
+    claim_alliance_emissions(&mut deps, Uint128::new(10));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "307");
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[2].value, "307");
+    claim_alliance_emissions(&mut deps, Uint128::new(4));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "3");
+}
+
+#[test]
+fn refresh_nft_weights_keeps_accrued_rewards() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("3")], None).unwrap();
+    mint_with_weight(deps.as_mut(), "2", vec![rarity("1")], None).unwrap();
+    mint_with_weight(deps.as_mut(), "3", vec![rarity("5")], None).unwrap();
+
+    // GIVEN NFTs minted before weights were introduced
+    for token_id in ["1", "2", "3"] {
+        NFT_WEIGHTS.remove(deps.as_mut().storage, token_id.to_string());
+    }
+    claim_alliance_emissions(&mut deps, Uint128::new(300));
+    break_nft(deps.as_mut(), "3");
+
+    // without a weight trait the NFTs keep a weight of 1
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::RefreshNftWeights {
+            token_ids: vec!["1".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(query_weight(deps.as_ref(), "1").total_active_weight, 2);
+    set_weight_trait(&mut deps, "rarity");
+
+    // WHEN anyone refreshes the weights
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::RefreshNftWeights {
+            token_ids: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+        },
+    )
+    .unwrap();
+
+    // EXPECT only the active NFTs to be updated
+    assert_eq!(
+        res,
+        Response::default().add_attributes(vec![
+            ("action", "refresh_nft_weights"),
+            ("token_id", "1"),
+            ("weight", "3"),
+            ("token_id", "2"),
+            ("weight", "1"),
+        ])
+    );
+    assert_eq!(query_weight(deps.as_ref(), "1").total_active_weight, 4);
+
+    // the rewards accrued before are kept and new rewards are shared by weight
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "100");
+    claim_alliance_emissions(&mut deps, Uint128::new(400));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "400");
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "200");
+}
+
+#[test]
+fn refresh_nft_weights_after_weight_trait_change() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    set_weight_trait(&mut deps, "rarity");
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("3")], None).unwrap();
+    mint_with_weight(deps.as_mut(), "2", vec![rarity("3")], Some(4)).unwrap();
+    let refresh = ExecuteCollectionMsg::RefreshNftWeights {
+        token_ids: vec!["1".to_string(), "2".to_string()],
+    };
+
+    // clearing the weight trait resets the derived weights to 1, explicit weights are kept
+    set_weight_trait(&mut deps, "");
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().weight_trait, None);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        refresh.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_weight(deps.as_ref(), "1"),
+        NftWeightResponse {
+            weight: 1,
+            total_active_weight: 5,
+        }
+    );
+    assert_eq!(query_weight(deps.as_ref(), "2").weight, 4);
+
+    // a new weight trait is read again from the traits
+    set_weight_trait(&mut deps, "rarity");
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), refresh).unwrap();
+    assert_eq!(
+        query_weight(deps.as_ref(), "1"),
+        NftWeightResponse {
+            weight: 3,
+            total_active_weight: 7,
+        }
+    );
+}
+
+#[test]
+fn refresh_nft_weights_pays_out_rewards_on_lower_weight() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    set_weight_trait(&mut deps, "rarity");
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("5")], None).unwrap();
+    mint_with_weight(deps.as_mut(), "2", vec![rarity("1")], None).unwrap();
+    claim_alliance_emissions(&mut deps, Uint128::new(600));
+
+    // GIVEN more rewards accrued than the checkpoint can hold with a weight of 1
+    set_weight_trait(&mut deps, "");
+
+    // WHEN the weight is lowered
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::RefreshNftWeights {
+            token_ids: vec!["1".to_string()],
+        },
+    )
+    .unwrap();
+
+    // EXPECT the accrued rewards to be paid out to the owner
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "refresh_nft_weights"),
+                ("token_id", "1"),
+                ("weight", "1"),
+                ("rewards", "500"),
+            ])
+            .add_message(BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: vec![Coin::new(500, LST_DENOM)],
+            })
+    );
+    assert_eq!(
+        query_weight(deps.as_ref(), "1"),
+        NftWeightResponse {
+            weight: 1,
+            total_active_weight: 2,
+        }
+    );
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes.last().unwrap().value, "0");
+
+    // new rewards are shared by the new weights
+    claim_alliance_emissions(&mut deps, Uint128::new(200));
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes.last().unwrap().value, "100");
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes.last().unwrap().value, "200");
+}
+
+#[test]
+fn mint_invalid_weight() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    set_weight_trait(&mut deps, "rarity");
+
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("1")], Some(0)).unwrap_err();
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("0")], None).unwrap_err();
+    mint_with_weight(deps.as_mut(), "1", vec![rarity("rare")], None).unwrap_err();
+}
+
+fn rarity(value: &str) -> Trait {
+    Trait {
+        display_type: None,
+        trait_type: "rarity".to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn set_weight_trait(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, weight_trait: &str) {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+            weight_trait: Some(weight_trait.to_string()),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+}
+
+fn query_weight(deps: Deps, token_id: &str) -> NftWeightResponse {
+    let msg = QueryCollectionMsg::NftWeight {
+        token_id: token_id.to_string(),
+    };
+    from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn sync_delegations_with_alliance_module() {
+    let mut deps = mock_dependencies_with_alliance();
+    setup_contract(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![
+                AllianceDelegation {
+                    validator: "val1".to_string(),
+                    amount: Uint128::new(100),
+                },
+                AllianceDelegation {
+                    validator: "val2".to_string(),
+                    amount: Uint128::new(200),
+                },
+                AllianceDelegation {
+                    validator: "val3".to_string(),
+                    amount: Uint128::new(300),
+                },
+            ],
+        }),
+    )
+    .unwrap();
+
+    // GIVEN val1 was slashed and the delegation to val3 failed
+    deps.querier
+        .set_alliance_delegation("val1", Uint128::new(90));
+    deps.querier
+        .set_alliance_delegation("val2", Uint128::new(200));
+
+    // WHEN anyone syncs the delegations
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::SyncDelegations {},
+    )
+    .unwrap();
+
+    // EXPECT the tracked stake to match the Alliance module
+    assert_eq!(
+        res,
+        Response::default().add_attributes(vec![
+            ("action", "sync_delegations"),
+            ("validator", "val1"),
+            ("tracked", "100"),
+            ("actual", "90"),
+            ("validator", "val3"),
+            ("tracked", "300"),
+            ("actual", "0"),
+        ])
+    );
+    assert_eq!(
+        VALS.load(&deps.storage, "val1".to_string()).unwrap(),
+        Uint128::new(90)
+    );
+    assert_eq!(
+        VALS.load(&deps.storage, "val2".to_string()).unwrap(),
+        Uint128::new(200)
+    );
+    assert!(!VALS.has(&deps.storage, "val3".to_string()));
+}
+
+#[test]
+fn sync_delegations_keeps_stake_on_query_error() {
+    let mut deps = mock_dependencies_with_alliance();
+    setup_contract(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![AllianceDelegation {
+                validator: "val1".to_string(),
+                amount: Uint128::new(100),
+            }],
+        }),
+    )
+    .unwrap();
+
+    // GIVEN the Alliance module fails to answer for val1
+    deps.querier.set_alliance_query_error("val1");
+
+    // WHEN anyone syncs the delegations
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::SyncDelegations {},
+    )
+    .unwrap_err();
+
+    // EXPECT the tracked stake to be kept
+    assert_eq!(
+        VALS.load(&deps.storage, "val1".to_string()).unwrap(),
+        Uint128::new(100)
+    );
+}
+
+#[test]
+fn undelegations_tracked_until_completed() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    let mut env = mock_env();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![AllianceDelegation {
+                validator: "val1".to_string(),
+                amount: Uint128::new(1_000),
+            }],
+        }),
+    )
+    .unwrap();
+    let undelegate = |amount: u128| {
+        ExecuteCollectionMsg::AllianceUndelegate(AllianceUndelegateMsg {
+            undelegations: vec![AllianceDelegation {
+                validator: "val1".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        })
+    };
+
+    let first_undelegation_time = env.block.time;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        undelegate(100),
+    )
+    .unwrap();
+    env.block.time = env.block.time.plus_days(7);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        undelegate(200),
+    )
+    .unwrap();
+
+    let unbondings = query_unbondings(deps.as_ref(), env.clone());
+    assert_eq!(
+        unbondings,
+        vec![
+            Unbonding {
+                validator: "val1".to_string(),
+                amount: Uint128::new(100),
+                initiated_at: first_undelegation_time,
+                completion_time: first_undelegation_time.plus_days(21),
+            },
+            Unbonding {
+                validator: "val1".to_string(),
+                amount: Uint128::new(200),
+                initiated_at: env.block.time,
+                completion_time: env.block.time.plus_days(21),
+            },
+        ]
+    );
+
+    // the first unbonding matured and is no longer listed
+    env.block.time = first_undelegation_time.plus_days(21);
+    let unbondings = query_unbondings(deps.as_ref(), env.clone());
+    assert_eq!(unbondings.len(), 1);
+    assert_eq!(unbondings[0].amount, Uint128::new(200));
+
+    // and it is removed from the state on the next undelegation
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        undelegate(300),
+    )
+    .unwrap();
+    let amounts: Vec<Uint128> = UNBONDINGS
+        .range(&deps.storage, None, None, Order::Ascending)
+        .map(|item| item.unwrap().1.amount)
+        .collect();
+    assert_eq!(amounts, vec![Uint128::new(200), Uint128::new(300)]);
+}
+
+fn query_unbondings(deps: Deps, env: Env) -> Vec<Unbonding> {
+    let res: UnbondingsResponse =
+        from_json(query(deps, env, QueryCollectionMsg::Unbondings {}).unwrap()).unwrap();
+    res.unbondings
+}
+
+#[test]
+fn rebalance_to_validator_targets() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![
+                AllianceDelegation {
+                    validator: "val1".to_string(),
+                    amount: Uint128::new(600),
+                },
+                AllianceDelegation {
+                    validator: "val2".to_string(),
+                    amount: Uint128::new(300),
+                },
+            ],
+        }),
+    )
+    .unwrap();
+    set_contract_balance(&mut deps, Coin::new(100, ASSET_DENOM));
+
+    // Cannot rebalance without targets
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::Rebalance {},
+    )
+    .unwrap_err();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+            validator_targets: Some(BTreeMap::from([
+                ("val1".to_string(), 1),
+                ("val2".to_string(), 1),
+                ("val3".to_string(), 2),
+            ])),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    // Only the owner can rebalance
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::Rebalance {},
+    )
+    .unwrap_err();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::Rebalance {},
+    )
+    .unwrap();
+
+    // the idle tokens are delegated first and the surplus of val1 and val2 is moved to val3
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Stargate {
+            type_url: "/alliance.alliance.MsgDelegate".to_string(),
+            value: Binary::from(
+                MsgDelegate {
+                    delegator_address: MOCK_CONTRACT_ADDR.to_string(),
+                    validator_address: "val3".to_string(),
+                    amount: Some(ProtoCoin {
+                        denom: ASSET_DENOM.to_string(),
+                        amount: "100".to_string(),
+                    }),
+                }
+                .encode_to_vec(),
+            ),
+        }
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Stargate {
+            type_url: "/alliance.alliance.MsgRedelegate".to_string(),
+            value: Binary::from(
+                MsgRedelegate {
+                    delegator_address: MOCK_CONTRACT_ADDR.to_string(),
+                    validator_src_address: "val1".to_string(),
+                    validator_dst_address: "val3".to_string(),
+                    amount: Some(ProtoCoin {
+                        denom: ASSET_DENOM.to_string(),
+                        amount: "350".to_string(),
+                    }),
+                }
+                .encode_to_vec(),
+            ),
+        }
+    );
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Stargate {
+            type_url: "/alliance.alliance.MsgRedelegate".to_string(),
+            value: Binary::from(
+                MsgRedelegate {
+                    delegator_address: MOCK_CONTRACT_ADDR.to_string(),
+                    validator_src_address: "val2".to_string(),
+                    validator_dst_address: "val3".to_string(),
+                    amount: Some(ProtoCoin {
+                        denom: ASSET_DENOM.to_string(),
+                        amount: "50".to_string(),
+                    }),
+                }
+                .encode_to_vec(),
+            ),
+        }
+    );
+    assert_eq!(
+        VALS.load(&deps.storage, "val1".to_string()).unwrap(),
+        Uint128::new(250)
+    );
+    assert_eq!(
+        VALS.load(&deps.storage, "val2".to_string()).unwrap(),
+        Uint128::new(250)
+    );
+    assert_eq!(
+        VALS.load(&deps.storage, "val3".to_string()).unwrap(),
+        Uint128::new(500)
+    );
+
+    // nothing to do once the targets are reached
+    set_contract_balance(&mut deps, Coin::new(0, ASSET_DENOM));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::Rebalance {},
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn keeper_fee_paid_to_harvester() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+
+    // the keeper fee is limited to 5%
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_keeper_config(Decimal::percent(6), 3600),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_keeper_config(Decimal::percent(1), 3600),
+    )
+    .unwrap();
+
+    // anyone can harvest and is passed on as the keeper
+    let mut env = mock_env();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteCollectionMsg::AllianceClaimRewards {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_json_binary(&ExecuteCollectionMsg::StakeRewardsCallback {
+                keeper: Some(Addr::unchecked("keeper")),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+
+    // but not before the harvest interval passed
+    env.block.time = env.block.time.plus_seconds(3599);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteCollectionMsg::AllianceClaimRewards {},
+    )
+    .unwrap_err();
+    env.block.time = env.block.time.plus_seconds(1);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper", &[]),
+        ExecuteCollectionMsg::AllianceClaimRewards {},
+    )
+    .unwrap();
+
+    // the claimed rewards are bonded with the hub
+    set_contract_balance(&mut deps, Coin::new(500, "uluna"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteCollectionMsg::StakeRewardsCallback {
+            keeper: Some(Addr::unchecked("keeper")),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "lst_hub".to_string(),
+                msg: to_json_binary(&eris::ExecuteMsg::Bond { receiver: None }).unwrap(),
+                funds: vec![Coin::new(500, "uluna")],
+            }),
+            BOND_REPLY_ID,
+        )]
+    );
+
+    // the keeper receives its share of the LST minted, LST sent by anyone else is not counted
+    set_contract_balance(&mut deps, Coin::new(5_000, LST_DENOM));
+    let res = reply(deps.as_mut(), mock_env(), bond_reply(Uint128::new(1_000))).unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "bond_reply"),
+                ("lst_received", "1000"),
+                ("keeper_fee", "10"),
+            ])
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(10, LST_DENOM)],
+                to_address: "keeper".to_string(),
+            }))
+    );
+    assert_eq!(
+        HARVESTS.load(&deps.storage, 0).unwrap().luna_bonded,
+        Uint128::new(500)
+    );
+    assert!(PENDING_HARVEST.may_load(&deps.storage).unwrap().is_none());
+
+    // a bond reply without the minted amount is rejected
+    let mut invalid_reply = bond_reply(Uint128::new(1_000));
+    invalid_reply.result = SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: None,
+    });
+    reply(deps.as_mut(), mock_env(), invalid_reply).unwrap_err();
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "990");
+}
+
+#[test]
+fn bond_with_steak_provider() {
+    let mut deps = mock_dependencies();
+    setup_contract_with_provider(deps.as_mut(), LstProvider::Steak);
+    mint(deps.as_mut(), "1");
+
+    set_contract_balance(&mut deps, Coin::new(500, "uluna"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteCollectionMsg::StakeRewardsCallback { keeper: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "lst_hub".to_string(),
+                msg: to_json_binary(&steak::ExecuteMsg::Bond { receiver: None }).unwrap(),
+                funds: vec![Coin::new(500, "uluna")],
+            }),
+            BOND_REPLY_ID,
+        )]
+    );
+
+    // the ERIS event is not emitted by a Steak hub
+    reply(deps.as_mut(), mock_env(), bond_reply(Uint128::new(400))).unwrap_err();
+
+    let steak_reply = Reply {
+        id: BOND_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![Event::new("wasm-steakhub/bonded")
+                .add_attribute("receiver", MOCK_CONTRACT_ADDR)
+                .add_attribute("uluna_bonded", "500")
+                .add_attribute("usteak_minted", "400")],
+            data: None,
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), steak_reply).unwrap();
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "400");
+}
+
+#[test]
+fn switch_lst_rescales_rewards() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+
+    let switch_lst = |lst: &str, min_received: u128| {
+        ExecuteCollectionMsg::SwitchLst(SwitchLstMsg {
+            lst_hub_address: "new_hub".to_string(),
+            lst_asset_info: AssetInfoUnchecked::native(lst),
+            lst_provider: LstProvider::Steak,
+            swap_contract: "pair".to_string(),
+            swap_msg: Binary::from(b"{\"swap\":{}}"),
+            min_received: Uint128::new(min_received),
+        })
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        switch_lst("bluna", 0),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        switch_lst(LST_DENOM, 0),
+    )
+    .unwrap_err();
+
+    // all held LST is swapped into the new LST
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        switch_lst("bluna", 1_250),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "pair".to_string(),
+                msg: Binary::from(b"{\"swap\":{}}"),
+                funds: vec![Coin::new(1_000, LST_DENOM)],
+            }),
+            SWITCH_LST_REPLY_ID,
+        )]
+    );
+    let config: Config =
+        from_json(query(deps.as_ref(), mock_env(), QueryCollectionMsg::Config {}).unwrap())
+            .unwrap();
+    assert_eq!(config.lst_asset_info, AssetInfo::native("bluna"));
+    assert_eq!(config.lst_hub, Hub(Addr::unchecked("new_hub")));
+    assert_eq!(config.lst_provider, LstProvider::Steak);
+
+    // less than the minimum received reverts the switch
+    set_contract_balance(&mut deps, Coin::new(0, LST_DENOM));
+    set_contract_balance(&mut deps, Coin::new(1_249, "bluna"));
+    let switch_reply = Reply {
+        id: SWITCH_LST_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), switch_reply.clone()).unwrap_err();
+
+    // the rewards of the NFTs are paid out in the new LST at the ratio of the swap,
+    // the failed reply reverted the switch, so its state is stored again
+    LST_SWITCH
+        .save(
+            deps.as_mut().storage,
+            &LstSwitch {
+                previous_lst_asset_info: AssetInfo::native(LST_DENOM),
+                swapped: Uint128::new(1_000),
+                balance_before: Uint128::zero(),
+                min_received: Uint128::new(1_250),
+            },
+        )
+        .unwrap();
+    set_contract_balance(&mut deps, Coin::new(1_250, "bluna"));
+    reply(deps.as_mut(), mock_env(), switch_reply).unwrap();
+    assert_eq!(LST_SWITCH_HARVEST_ID.load(&deps.storage).unwrap(), 1);
+    assert_eq!(
+        query_nft(deps.as_ref(), "1").extension.attributes.unwrap()[2].value,
+        "625"
+    );
+
+    // new harvests are distributed without conversion
+    PENDING_HARVEST
+        .save(
+            deps.as_mut().storage,
+            &PendingHarvest {
+                luna_bonded: Uint128::zero(),
+                keeper: None,
+            },
+        )
+        .unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: BOND_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![
+                    Event::new("wasm-steakhub/bonded").add_attribute("usteak_minted", "100")
+                ],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    let res = break_nft(deps.as_mut(), "1");
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(675, "bluna")],
+            to_address: "owner".to_string(),
+        })
+    );
+}
+
+#[test]
+fn switch_lst_without_balance() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+    break_nft(deps.as_mut(), "1");
+
+    // GIVEN no LST is held anymore
+    set_contract_balance(&mut deps, Coin::new(0, LST_DENOM));
+
+    // WHEN the LST is switched
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::SwitchLst(SwitchLstMsg {
+            lst_hub_address: "new_hub".to_string(),
+            lst_asset_info: AssetInfoUnchecked::native("bluna"),
+            lst_provider: LstProvider::Steak,
+            swap_contract: "pair".to_string(),
+            swap_msg: Binary::from(b"{\"swap\":{}}"),
+            min_received: Uint128::zero(),
+        }),
+    )
+    .unwrap();
+
+    // EXPECT no swap and the earlier harvests to be excluded from the reward rate
+    assert_eq!(res.messages, vec![]);
+    assert!(!LST_SWITCH.exists(&deps.storage));
+    assert_eq!(LST_SWITCH_HARVEST_ID.load(&deps.storage).unwrap(), 1);
+}
+
+#[test]
+fn break_nft_with_unbond_payout() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+
+    let break_msg = |token_id: &str| ExecuteCollectionMsg::BreakNft {
+        token_id: token_id.to_string(),
+        payout: BreakPayout::Unbond,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        break_msg("1"),
+    )
+    .unwrap_err();
+
+    // the rewards are queued for unbonding on the hub for the owner
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        break_msg("1"),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "break_nft"),
+                ("token_id", "1"),
+                ("rewards", "500"),
+                ("payout", "unbond"),
+            ])
+            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "lst_hub".to_string(),
+                msg: to_json_binary(&eris::ExecuteMsg::QueueUnbond {
+                    receiver: Some("owner".to_string()),
+                })
+                .unwrap(),
+                funds: vec![Coin::new(500, LST_DENOM)],
+            }))
+    );
+
+    // a cw20 LST is sent to the hub with the queue unbond hook
+    let mut cfg = CONFIG.load(&deps.storage).unwrap();
+    cfg.lst_asset_info = AssetInfo::cw20(Addr::unchecked("ampluna_token"));
+    CONFIG.save(deps.as_mut().storage, &cfg).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        break_msg("2"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        Asset::cw20(Addr::unchecked("ampluna_token"), 500u128)
+            .send_msg(
+                "lst_hub",
+                to_json_binary(&eris::ReceiveMsg::QueueUnbond {
+                    receiver: Some("owner".to_string()),
+                })
+                .unwrap(),
+            )
+            .unwrap()
+    );
+}
+
+#[test]
+fn break_nfts_with_unbond_payout() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+
+    // the summed rewards are queued for unbonding in a single message
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::BreakNfts {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+            payout: BreakPayout::Unbond,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "break_nfts"),
+                ("token_id", "1"),
+                ("rewards", "500"),
+                ("token_id", "2"),
+                ("rewards", "500"),
+                ("total_rewards", "1000"),
+                ("payout", "unbond"),
+            ])
+            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "lst_hub".to_string(),
+                msg: to_json_binary(&eris::ExecuteMsg::QueueUnbond {
+                    receiver: Some("owner".to_string()),
+                })
+                .unwrap(),
+                funds: vec![Coin::new(1_000, LST_DENOM)],
+            }))
+    );
+}
+
+#[test]
+fn reward_recipient_receives_rewards() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+
+    let set_recipient = |recipient: Option<&str>| ExecuteCollectionMsg::SetRewardRecipient {
+        token_id: "1".to_string(),
+        recipient: recipient.map(|r| r.to_string()),
+    };
+    let query_recipient = |deps: Deps| -> Addr {
+        let res: RewardRecipientResponse = from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryCollectionMsg::RewardRecipient {
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.recipient
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        set_recipient(Some("cold")),
+    )
+    .unwrap_err();
+    assert_eq!(query_recipient(deps.as_ref()), Addr::unchecked("owner"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_recipient(Some("cold")),
+    )
+    .unwrap();
+    assert_eq!(query_recipient(deps.as_ref()), Addr::unchecked("cold"));
+
+    // the rewards of each NFT go to its recipient
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ClaimRewards {
+            token_ids: vec!["1".to_string(), "2".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(500, LST_DENOM)],
+                to_address: "cold".to_string(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(500, LST_DENOM)],
+                to_address: "owner".to_string(),
+            }),
+        ]
+    );
+
+    // the recipient is removed when the NFT is transferred
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::TransferNft {
+            recipient: "owner2".to_string(),
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(query_recipient(deps.as_ref()), Addr::unchecked("owner2"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        set_recipient(Some("vault")),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        set_recipient(None),
+    )
+    .unwrap();
+    assert_eq!(query_recipient(deps.as_ref()), Addr::unchecked("owner2"));
+}
+
+#[test]
+fn settle_rewards_on_transfer() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+
+    // by default the pending rewards pass to the new owner
+    let transfer = |token_id: &str| ExecuteCollectionMsg::TransferNft {
+        recipient: "buyer".to_string(),
+        token_id: token_id.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        transfer("2"),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        query_nft(deps.as_ref(), "2").extension.attributes.unwrap()[2].value,
+        "500"
+    );
+
+    let update_config = ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+        settle_rewards_on_transfer: Some(true),
+        ..Default::default()
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    // transfers by an operator settle the rewards with the previous owner
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ApproveAll {
+            operator: "market".to_string(),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("market", &[]),
+        transfer("1"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(500, LST_DENOM)],
+            to_address: "owner".to_string(),
+        })
+    );
+    assert_eq!(
+        query_nft(deps.as_ref(), "1").extension.attributes.unwrap()[2].value,
+        "0"
+    );
+
+    // an unauthorised transfer fails before anything is paid out
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("market", &[]),
+        transfer("1"),
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn approved_spenders_and_operators_break_and_claim() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+
+    let break_msg = ExecuteCollectionMsg::BreakNft {
+        token_id: "1".to_string(),
+        payout: BreakPayout::Lst,
+    };
+    let claim_msg = ExecuteCollectionMsg::ClaimRewards {
+        token_ids: vec!["2".to_string()],
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        break_msg.clone(),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        claim_msg.clone(),
+    )
+    .unwrap_err();
+
+    // a spender approved for the token breaks it, the rewards still go to the owner
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::Approve {
+            spender: "vault".to_string(),
+            token_id: "1".to_string(),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        break_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(500, LST_DENOM)],
+            to_address: "owner".to_string(),
+        })
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        claim_msg.clone(),
+    )
+    .unwrap_err();
+
+    // an operator of the owner claims the rewards of any of its NFTs
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::ApproveAll {
+            operator: "vault".to_string(),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        claim_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            amount: vec![Coin::new(500, LST_DENOM)],
+            to_address: "owner".to_string(),
+        })
+    );
+
+    // the reward recipient can only be set by the owner
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vault", &[]),
+        ExecuteCollectionMsg::SetRewardRecipient {
+            token_id: "2".to_string(),
+            recipient: Some("vault".to_string()),
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn burn_settles_rewards_and_removes_state() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+    break_nft(deps.as_mut(), "2");
+
+    let burn = |token_id: &str| ExecuteCollectionMsg::Burn {
+        token_id: token_id.to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        burn("1"),
+    )
+    .unwrap_err();
+
+    // an active NFT is paid out before it is burned
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        burn("1"),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attributes(vec![
+                ("action", "burn"),
+                ("sender", "owner"),
+                ("token_id", "1"),
+                ("rewards", "500"),
+            ])
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(500, LST_DENOM)],
+                to_address: "owner".to_string(),
+            }))
+    );
+    let reward_state: RewardStateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::RewardState {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(reward_state.num_active_nfts, 0);
+    assert_eq!(reward_state.total_active_weight, 0);
+    assert!(!NFT_BALANCE_CLAIMED.has(&deps.storage, "1".to_string()));
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryCollectionMsg::NftInfo {
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    // a broken NFT has no rewards left
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        burn("2"),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(!BROKEN_NFTS.has(&deps.storage, "2".to_string()));
+    let num_tokens: NumTokensResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryCollectionMsg::NumTokens {}).unwrap())
+            .unwrap();
+    assert_eq!(num_tokens.count, 0);
+}
+
+#[test]
+fn repair_nft() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+    break_nft(deps.as_mut(), "1");
+
+    let repair = |token_id: &str| ExecuteCollectionMsg::RepairNft {
+        token_id: token_id.to_string(),
+    };
+    let fee = [Coin::new(100, "uluna")];
+    // repairing is disabled until the repair fee is set
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &fee),
+        repair("1"),
+    )
+    .unwrap_err();
+
+    let set_repair_fee = |repair_fee: AssetUnchecked| {
+        ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+            repair_fee: Some(repair_fee),
+            ..Default::default()
+        })
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_repair_fee(AssetUnchecked::native(ASSET_DENOM, 100u128)),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_repair_fee(AssetUnchecked::native("uluna", 100u128)),
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &fee),
+        repair("2"),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &fee),
+        repair("1"),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[Coin::new(99, "uluna")]),
+        repair("1"),
+    )
+    .unwrap_err();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &fee),
+        repair("1"),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_message(CosmosMsg::Bank(BankMsg::Send {
+                amount: vec![Coin::new(100, "uluna")],
+                to_address: "dao_treasury".to_string(),
+            }))
+            .add_attributes(vec![
+                ("action", "repair_nft"),
+                ("token_id", "1"),
+                ("fee", "native:uluna:100"),
+            ])
+    );
+    assert!(!BROKEN_NFTS.has(&deps.storage, "1".to_string()));
+    let reward_state: RewardStateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::RewardState {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(reward_state.num_active_nfts, 2);
+    assert_eq!(reward_state.total_active_weight, 2);
+
+    // the repaired NFT only accrues the rewards harvested after the repair
+    assert_eq!(
+        query_nft(deps.as_ref(), "1").extension.attributes.unwrap()[2].value,
+        "0"
+    );
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+    assert_eq!(
+        query_nft(deps.as_ref(), "1").extension.attributes.unwrap()[2].value,
+        "500"
+    );
+    assert_eq!(
+        query_nft(deps.as_ref(), "2").extension.attributes.unwrap()[2].value,
+        "1000"
+    );
+
+    // cw20 fees are transferred from the sender
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_repair_fee(AssetUnchecked::cw20("fee_token", 50u128)),
+    )
+    .unwrap();
+    break_nft(deps.as_mut(), "2");
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        repair("2"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(
+            Asset::cw20(Addr::unchecked("fee_token"), 50u128)
+                .transfer_from_msg("owner", "dao_treasury")
+                .unwrap()
+        )]
+    );
+}
+
+#[test]
+fn forge_broken_nfts() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    mint(deps.as_mut(), "3");
+    claim_alliance_emissions(&mut deps, Uint128::new(900));
+    break_nft(deps.as_mut(), "1");
+    break_nft(deps.as_mut(), "2");
+
+    let forge = |token_ids: &[&str]| ExecuteCollectionMsg::Forge {
+        token_ids: token_ids.iter().map(|id| id.to_string()).collect(),
+    };
+    // forging is disabled until the number of inputs is set
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(&["1", "2"]),
+    )
+    .unwrap_err();
+
+    let set_forge_inputs = |forge_inputs: u32| {
+        ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+            forge_inputs: Some(forge_inputs),
+            ..Default::default()
+        })
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_forge_inputs(1),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_forge_inputs(2),
+    )
+    .unwrap();
+
+    // no metadata to forge from yet
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(&["1", "2"]),
+    )
+    .unwrap_err();
+
+    let forge_metadata = |token_id: &str| MinterExtension {
+        token_id: token_id.to_string(),
+        extension: Extension {
+            name: Some(format!("Forged {}", token_id)),
+            ..query_nft(deps.as_ref(), "3").extension
+        },
+    };
+    let metadata = vec![forge_metadata("forged-1"), forge_metadata("forged-2")];
+    let duplicate = vec![forge_metadata("3")];
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteCollectionMsg::AppendForgeMetadata(metadata.clone()),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AppendForgeMetadata(duplicate),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AppendForgeMetadata(metadata),
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(&["1"]),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(&["1", "1"]),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(&["1", "3"]),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        forge(&["1", "2"]),
+    )
+    .unwrap_err();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(&["1", "2"]),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default().add_attributes(vec![
+            ("action", "burn"),
+            ("sender", "owner"),
+            ("token_id", "1"),
+            ("action", "burn"),
+            ("sender", "owner"),
+            ("token_id", "2"),
+            ("action", "forge"),
+            ("owner", "owner"),
+            ("token_id", "forged-1"),
+            ("forged_from", "1,2"),
+        ])
+    );
+    assert!(!BROKEN_NFTS.has(&deps.storage, "1".to_string()));
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryCollectionMsg::NftInfo {
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap_err();
+    let num_tokens: NumTokensResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryCollectionMsg::NumTokens {}).unwrap())
+            .unwrap();
+    assert_eq!(num_tokens.count, 2);
+
+    // the forged NFT is active and lists the burned NFTs
+    let forged = query_nft(deps.as_ref(), "forged-1");
+    assert_eq!(forged.extension.name, Some("Forged forged-1".to_string()));
+    let traits = forged.extension.attributes.unwrap();
+    assert!(traits.contains(&Trait {
+        display_type: None,
+        trait_type: "Forged".to_string(),
+        value: "1,2".to_string(),
+    }));
+    assert_eq!(traits.last().unwrap().value, "0");
+    claim_alliance_emissions(&mut deps, Uint128::new(1_000));
+    assert_eq!(
+        query_nft(deps.as_ref(), "forged-1")
+            .extension
+            .attributes
+            .unwrap()
+            .last()
+            .unwrap()
+            .value,
+        "500"
+    );
+}
+
+#[test]
+fn forge_skips_removed_and_minted_metadata() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    for token_id in ["1", "2", "3", "4"] {
+        mint(deps.as_mut(), token_id);
+        break_nft(deps.as_mut(), token_id);
+    }
+    let update_config = ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+        forge_inputs: Some(2),
+        ..Default::default()
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    let metadata = ["a", "b", "c"]
+        .iter()
+        .map(|token_id| MinterExtension {
+            token_id: token_id.to_string(),
+            extension: query_nft(deps.as_ref(), "1").extension,
+        })
+        .collect();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AppendForgeMetadata(metadata),
+    )
+    .unwrap();
+
+    // "a" is minted after it was added to the pool and "b" is removed by the owner
+    mint(deps.as_mut(), "a");
+    let remove = ExecuteCollectionMsg::RemoveForgeMetadata {
+        token_ids: vec!["b".to_string()],
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        remove.clone(),
+    )
+    .unwrap_err();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), remove).unwrap();
+    assert_eq!(
+        res,
+        Response::default().add_attributes(vec![
+            ("action", "remove_forge_metadata"),
+            ("token_ids", "b"),
+        ])
+    );
+
+    let forge = |token_ids: [&str; 2]| ExecuteCollectionMsg::Forge {
+        token_ids: token_ids.iter().map(|id| id.to_string()).collect(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(["1", "2"]),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&Attribute::new("token_id", "c")));
+    assert!(!FORGE_METADATA.has(&deps.storage, "a".to_string()));
+    assert_eq!(query_nft(deps.as_ref(), "a").extension.name, None);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        forge(["3", "4"]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoForgeMetadata {}));
+}
+
+fn update_keeper_config(keeper_fee: Decimal, harvest_interval: u64) -> ExecuteCollectionMsg {
+    ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+        keeper_fee: Some(keeper_fee),
+        harvest_interval: Some(harvest_interval),
+        ..Default::default()
+    })
+}
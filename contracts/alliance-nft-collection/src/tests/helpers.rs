@@ -1,17 +1,33 @@
 use crate::contract::execute::execute;
 use crate::contract::instantiate::instantiate;
 use crate::contract::query::query;
-use crate::state::TEMP_BALANCE;
-use alliance_nft_packages::Extension;
-use alliance_nft_packages::execute::{MintMsg, ExecuteCollectionMsg};
+use crate::contract::reply::{reply, BOND_REPLY_ID};
+use crate::state::PENDING_HARVEST;
+use alliance_nft_packages::errors::ContractError;
+use alliance_nft_packages::execute::{BreakPayout, ExecuteCollectionMsg, MintMsg};
 use alliance_nft_packages::instantiate::InstantiateCollectionMsg;
+use alliance_nft_packages::lst::LstProvider;
 use alliance_nft_packages::query::QueryCollectionMsg;
-use alliance_nft_packages::state::Trait;
-use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
-use cosmwasm_std::{from_binary, Addr, Deps, DepsMut, Response, Uint128};
+use alliance_nft_packages::state::{PendingHarvest, Trait};
+use alliance_nft_packages::Extension;
+use cosmwasm_std::testing::{
+    mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    from_json, Addr, Coin, Decimal, Deps, DepsMut, Event, OwnedDeps, Reply, Response,
+    SubMsgResponse, SubMsgResult, Uint128,
+};
 use cw721::NftInfoResponse;
+use cw_asset::AssetInfoUnchecked;
+
+pub const LST_DENOM: &str = "ampluna";
+pub const ASSET_DENOM: &str = "factory/cosmos2contract/AllianceNFT";
 
 pub fn setup_contract(deps: DepsMut) -> Response {
+    setup_contract_with_provider(deps, LstProvider::Eris)
+}
+
+pub fn setup_contract_with_provider(deps: DepsMut, lst_provider: LstProvider) -> Response {
     let info = mock_info("admin", &[]);
     let env = mock_env();
 
@@ -20,40 +36,59 @@ pub fn setup_contract(deps: DepsMut) -> Response {
         name: "Collection Name".to_string(),
         symbol: "CNA".to_string(),
         owner: Addr::unchecked("owner"),
+        dao_treasury_address: "dao_treasury".to_string(),
+        dao_treasury_share: Decimal::zero(),
+        lst_hub_address: "lst_hub".to_string(),
+        lst_asset_info: AssetInfoUnchecked::native(LST_DENOM),
+        lst_provider,
     };
     instantiate(deps, env, info, init_msg).unwrap()
 }
 
 pub fn mint(deps: DepsMut, token_id: &str) -> Response {
+    let traits = vec![Trait {
+        display_type: None,
+        trait_type: "trait_type".to_string(),
+        value: "value".to_string(),
+    }];
+    mint_with_weight(deps, token_id, traits, None).unwrap()
+}
+
+pub fn mint_with_weight(
+    deps: DepsMut,
+    token_id: &str,
+    traits: Vec<Trait>,
+    weight: Option<u64>,
+) -> Result<Response, ContractError> {
     let info = mock_info("minter", &[]);
     let env = mock_env();
     let msg = ExecuteCollectionMsg::Mint(MintMsg {
         owner: "owner".to_string(),
         token_id: token_id.to_string(),
         token_uri: None,
+        weight,
         extension: Extension {
             image: Some("image".to_string()),
             image_data: None,
             external_url: None,
             description: None,
             name: None,
-            attributes: Some(vec![Trait {
-                display_type: None,
-                trait_type: "trait_type".to_string(),
-                value: "value".to_string(),
-            }]),
+            attributes: Some(traits),
             background_color: None,
             animation_url: None,
             youtube_url: None,
         },
     });
-    execute(deps, env, info, msg).unwrap()
+    execute(deps, env, info, msg)
 }
 
 pub fn break_nft(deps: DepsMut, token_id: &str) -> Response {
     let info = mock_info("owner", &[]);
     let env = mock_env();
-    let msg = ExecuteCollectionMsg::BreakNft(token_id.to_string());
+    let msg = ExecuteCollectionMsg::BreakNft {
+        token_id: token_id.to_string(),
+        payout: BreakPayout::Lst,
+    };
     execute(deps, env, info, msg).unwrap()
 }
 
@@ -61,24 +96,58 @@ pub fn query_nft(deps: Deps, token_id: &str) -> NftInfoResponse<Extension> {
     let msg = QueryCollectionMsg::NftInfo {
         token_id: token_id.to_string(),
     };
-    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
 }
 
-pub fn claim_alliance_emissions(deps: DepsMut, rewards: Uint128) {
-    let contract_balance = deps
+// Simulates the LST minted by the hub for the claimed alliance
+// emissions and runs the bond reply over the minted amount.
+pub fn claim_alliance_emissions(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    rewards: Uint128,
+) {
+    let lst_balance = deps
+        .as_ref()
         .querier
-        .query_balance(MOCK_CONTRACT_ADDR, "uluna")
-        .unwrap();
-    if rewards > contract_balance.amount {
-        panic!("Contract balance is not enough to claim rewards");
-    }
+        .query_balance(MOCK_CONTRACT_ADDR, LST_DENOM)
+        .unwrap()
+        .amount;
+    set_contract_balance(deps, Coin::new((lst_balance + rewards).u128(), LST_DENOM));
 
-    TEMP_BALANCE
-        .save(deps.storage, &(contract_balance.amount - rewards))
+    let pending = PendingHarvest {
+        luna_bonded: Uint128::zero(),
+        keeper: None,
+    };
+    PENDING_HARVEST
+        .save(deps.as_mut().storage, &pending)
         .unwrap();
+    reply(deps.as_mut(), mock_env(), bond_reply(rewards)).unwrap();
+}
 
-    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
-    let env = mock_env();
-    let msg = ExecuteCollectionMsg::UpdateRewardsCallback {};
-    execute(deps, env, info, msg).unwrap();
+// Reply of the hub to the bond message, minting the given amount of LST
+pub fn bond_reply(lst_minted: Uint128) -> Reply {
+    Reply {
+        id: BOND_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![Event::new("wasm-erishub/bonded")
+                .add_attribute("receiver", MOCK_CONTRACT_ADDR)
+                .add_attribute("token_bonded", lst_minted)
+                .add_attribute("ustake_minted", lst_minted)],
+            data: None,
+        }),
+    }
+}
+
+// Overrides the contract balance of a single denom,
+// keeping the balances of every other denom untouched.
+pub fn set_contract_balance(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, coin: Coin) {
+    let mut balances: Vec<Coin> = deps
+        .as_ref()
+        .querier
+        .query_all_balances(MOCK_CONTRACT_ADDR)
+        .unwrap()
+        .into_iter()
+        .filter(|c| c.denom != coin.denom)
+        .collect();
+    balances.push(coin);
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, balances);
 }
@@ -2,14 +2,16 @@ use crate::contract::instantiate::{instantiate, CONTRACT_NAME, CONTRACT_VERSION}
 use crate::contract::reply::reply;
 
 use alliance_nft_packages::instantiate::InstantiateCollectionMsg;
+use alliance_nft_packages::lst::LstProvider;
 use cosmwasm_std::testing::{
     mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
 };
 use cosmwasm_std::{
-    Addr, Binary, CosmosMsg, Empty, Env, MessageInfo, OwnedDeps, Reply, Response, SubMsg,
+    Addr, Binary, CosmosMsg, Decimal, Empty, Env, MessageInfo, OwnedDeps, Reply, Response, SubMsg,
     SubMsgResponse, SubMsgResult,
 };
 use cw2::get_contract_version;
+use cw_asset::AssetInfoUnchecked;
 use terra_proto_rs::cosmos::bank::v1beta1::{DenomUnit, Metadata};
 use terra_proto_rs::cosmos::base::v1beta1::Coin;
 use terra_proto_rs::osmosis::tokenfactory::v1beta1::{MsgMint, MsgSetDenomMetadata};
@@ -39,6 +41,11 @@ pub fn intantiate_with_reply() -> (
         name: "Collection Name".to_string(),
         symbol: "CNA".to_string(),
         owner: Addr::unchecked("owner"),
+        dao_treasury_address: "dao_treasury".to_string(),
+        dao_treasury_share: Decimal::zero(),
+        lst_hub_address: "lst_hub".to_string(),
+        lst_asset_info: AssetInfoUnchecked::native("ampluna"),
+        lst_provider: LstProvider::Eris,
     };
     let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -0,0 +1,59 @@
+use crate::contract::migrate::migrate;
+use crate::state::{ACTIVE_WEIGHT, REWARD_INDEX};
+use crate::tests::helpers::{mint, query_nft, setup_contract};
+use alliance_nft_packages::migrate::MigrateMsg;
+use cosmwasm_std::testing::{mock_dependencies, mock_env};
+use cosmwasm_std::{Decimal256, Uint128};
+use cw2::get_contract_version;
+use cw_storage_plus::{Item, Map};
+
+#[test]
+fn migrate_reward_balance_to_reward_index() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+
+    // GIVEN the reward accounting of version 1.1.0
+    let reward_balance: Item<Uint128> = Item::new("rb");
+    let nft_balance_claimed: Map<String, Uint128> = Map::new("nb");
+    REWARD_INDEX.remove(deps.as_mut().storage);
+    ACTIVE_WEIGHT.remove(deps.as_mut().storage);
+    reward_balance
+        .save(deps.as_mut().storage, &Uint128::new(1_000))
+        .unwrap();
+    nft_balance_claimed
+        .save(deps.as_mut().storage, "1".to_string(), &Uint128::zero())
+        .unwrap();
+    nft_balance_claimed
+        .save(deps.as_mut().storage, "2".to_string(), &Uint128::new(400))
+        .unwrap();
+
+    // WHEN migrating to 1.2.0
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            version: "1.2.0".to_string(),
+            nft_collection_code_id: None,
+            version110_data: None,
+        },
+    )
+    .unwrap();
+
+    // EXPECT the reward balance to be the new index and the claimed balances to be kept
+    assert_eq!(
+        get_contract_version(&deps.storage).unwrap().version,
+        "1.2.0"
+    );
+    let reward_index = REWARD_INDEX.load(&deps.storage).unwrap();
+    assert_eq!(reward_index.index, Decimal256::from_ratio(1_000u128, 1u128));
+    assert_eq!(reward_index.remainder, Decimal256::zero());
+    assert!(reward_balance.may_load(&deps.storage).unwrap().is_none());
+    assert_eq!(ACTIVE_WEIGHT.load(&deps.storage).unwrap(), 2);
+
+    let attributes = query_nft(deps.as_ref(), "1").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "1000");
+    let attributes = query_nft(deps.as_ref(), "2").extension.attributes.unwrap();
+    assert_eq!(attributes[2].value, "600");
+}
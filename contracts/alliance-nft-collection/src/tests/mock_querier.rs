@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_json, Binary, ContractResult, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest,
+    SystemError, SystemResult, Uint128, WasmQuery,
+};
+use terra_proto_rs::alliance::alliance::{
+    DelegationResponse, QueryAllianceDelegationRequest, QueryAllianceDelegationResponse,
+    QueryAllianceDelegationRewardsRequest, QueryAllianceDelegationRewardsResponse,
+};
+use terra_proto_rs::cosmos::base::v1beta1::Coin;
+use terra_proto_rs::traits::Message;
+
+// Mocks the Stargate queries to the Alliance module on top of the default querier
+pub struct WasmMockQuerier {
+    base: MockQuerier,
+    alliance_delegations: HashMap<String, Uint128>,
+    alliance_rewards: HashMap<String, Uint128>,
+    alliance_query_errors: HashSet<String>,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_json(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {e}"),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        match &request {
+            QueryRequest::Stargate { path, data } => self.handle_stargate(path, data),
+            _ => self.base.handle_query(&request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    fn handle_stargate(&self, path: &str, data: &Binary) -> QuerierResult {
+        if path == "/alliance.alliance.Query/AllianceDelegationRewards" {
+            return self.handle_alliance_rewards(data);
+        }
+        if path != "/alliance.alliance.Query/AllianceDelegation" {
+            return SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: path.to_string(),
+            });
+        }
+
+        let request = QueryAllianceDelegationRequest::decode(data.as_slice()).unwrap();
+        if self.alliance_query_errors.contains(&request.validator_addr) {
+            return SystemResult::Ok(ContractResult::Err("module unavailable".to_string()));
+        }
+        match self.alliance_delegations.get(&request.validator_addr) {
+            Some(amount) => {
+                let res = QueryAllianceDelegationResponse {
+                    delegation: Some(DelegationResponse {
+                        delegation: None,
+                        balance: Some(Coin {
+                            denom: request.denom,
+                            amount: amount.to_string(),
+                        }),
+                    }),
+                };
+                SystemResult::Ok(ContractResult::Ok(Binary::from(res.encode_to_vec())))
+            }
+            None => SystemResult::Ok(ContractResult::Err("delegation not found".to_string())),
+        }
+    }
+
+    fn handle_alliance_rewards(&self, data: &Binary) -> QuerierResult {
+        let request = QueryAllianceDelegationRewardsRequest::decode(data.as_slice()).unwrap();
+        if self.alliance_query_errors.contains(&request.validator_addr) {
+            return SystemResult::Ok(ContractResult::Err("module unavailable".to_string()));
+        }
+        match self.alliance_rewards.get(&request.validator_addr) {
+            Some(amount) => {
+                let res = QueryAllianceDelegationRewardsResponse {
+                    rewards: vec![Coin {
+                        denom: "uluna".to_string(),
+                        amount: amount.to_string(),
+                    }],
+                };
+                SystemResult::Ok(ContractResult::Ok(Binary::from(res.encode_to_vec())))
+            }
+            None => SystemResult::Ok(ContractResult::Err("delegation not found".to_string())),
+        }
+    }
+
+    pub fn set_alliance_query_error(&mut self, validator: &str) {
+        self.alliance_query_errors.insert(validator.to_string());
+    }
+
+    pub fn set_alliance_rewards(&mut self, validator: &str, amount: Uint128) {
+        self.alliance_rewards.insert(validator.to_string(), amount);
+    }
+
+    pub fn update_wasm<WH>(&mut self, handler: WH)
+    where
+        WH: Fn(&WasmQuery) -> QuerierResult + 'static,
+    {
+        self.base.update_wasm(handler);
+    }
+
+    pub fn update_balance(&mut self, addr: &str, balance: Vec<cosmwasm_std::Coin>) {
+        self.base.update_balance(addr, balance);
+    }
+
+    pub fn set_alliance_delegation(&mut self, validator: &str, amount: Uint128) {
+        self.alliance_delegations
+            .insert(validator.to_string(), amount);
+    }
+}
+
+pub fn mock_dependencies_with_alliance() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: WasmMockQuerier {
+            base: MockQuerier::new(&[(MOCK_CONTRACT_ADDR, &[])]),
+            alliance_delegations: HashMap::new(),
+            alliance_rewards: HashMap::new(),
+            alliance_query_errors: HashSet::new(),
+        },
+        custom_query_type: PhantomData,
+    }
+}
@@ -1,4 +1,6 @@
-mod instantiate;
 mod execute;
+mod helpers;
+mod instantiate;
+mod migrate;
+mod mock_querier;
 mod query;
-mod helpers;
\ No newline at end of file
@@ -1,5 +1,31 @@
-use alliance_nft_packages::query::QueryCollectionMsg;
+use crate::contract::execute::execute;
 use crate::contract::query::query;
+use crate::state::{add_harvest, CONFIG, LAST_HARVEST, LST_SWITCH_HARVEST_ID};
+use crate::tests::helpers::{
+    break_nft, claim_alliance_emissions, mint, set_contract_balance, setup_contract,
+};
+use crate::tests::mock_querier::mock_dependencies_with_alliance;
+use alliance_nft_packages::eris::{self, Hub, StateResponse, UnbondRequestsByUserResponseItem};
+use alliance_nft_packages::errors::ContractError;
+use alliance_nft_packages::execute::{
+    AllianceDelegateMsg, AllianceDelegation, ExecuteCollectionMsg, UpdateConfigMsg,
+};
+use alliance_nft_packages::lst::LstProvider;
+use alliance_nft_packages::query::{
+    BrokenNftsResponse, DelegationDriftResponse, HarvestHistoryResponse, OwnerPortfolioResponse,
+    PortfolioNft, QueryCollectionMsg, RewardRateResponse, RewardStateResponse,
+    SimulateBreakResponse, SimulateHarvestResponse, SimulatedBreak, UnbondRequestsResponse,
+    ValidatorDrift, ValidatorStake, ValidatorsResponse,
+};
+use alliance_nft_packages::state::{Config, Harvest, RewardIndex, DEFAULT_UNBONDING_PERIOD};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Coin, ContractResult, Decimal, Decimal256, StdError,
+    StdResult, SystemResult, Uint128, WasmQuery,
+};
+use cw_asset::{Asset, AssetInfo, AssetInfoUnchecked};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
 use super::instantiate::intantiate_with_reply;
 
@@ -7,7 +33,12 @@ use super::instantiate::intantiate_with_reply;
 fn test_query_info_and_config() {
     let (deps, env, _) = intantiate_with_reply();
 
-    let contract_info_res = query(deps.as_ref(), env.clone(), QueryCollectionMsg::ContractInfo {}).unwrap();
+    let contract_info_res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryCollectionMsg::ContractInfo {},
+    )
+    .unwrap();
     let contract_conf_res = query(deps.as_ref(), env, QueryCollectionMsg::Config {}).unwrap();
 
     assert_eq!(
@@ -15,7 +46,617 @@ fn test_query_info_and_config() {
         "{\"name\":\"Collection Name\",\"symbol\":\"CNA\"}".as_bytes()
     );
     assert_eq!(
-        contract_conf_res,
-        "{\"owner\":\"owner\",\"asset_denom\":\"factory/cosmos2contract/AllianceNFT\"}".as_bytes()
+        from_json::<Config>(&contract_conf_res).unwrap(),
+        Config {
+            owner: Addr::unchecked("owner"),
+            asset_denom: "factory/cosmos2contract/AllianceNFT".to_string(),
+            dao_treasury_address: Addr::unchecked("dao_treasury"),
+            dao_treasury_share: Decimal::zero(),
+            lst_hub: Hub(Addr::unchecked("lst_hub")),
+            lst_asset_info: AssetInfo::native("ampluna"),
+            lst_provider: LstProvider::Eris,
+            reward_assets: vec![],
+            weight_trait: None,
+            unbonding_period: DEFAULT_UNBONDING_PERIOD,
+            validator_targets: BTreeMap::new(),
+            keeper_fee: Decimal::zero(),
+            harvest_interval: 0,
+            settle_rewards_on_transfer: false,
+            repair_fee: None,
+            forge_inputs: None,
+        }
     );
 }
+
+#[test]
+fn test_query_validators() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![
+                AllianceDelegation {
+                    validator: "val2".to_string(),
+                    amount: Uint128::new(200),
+                },
+                AllianceDelegation {
+                    validator: "val1".to_string(),
+                    amount: Uint128::new(100),
+                },
+                AllianceDelegation {
+                    validator: "val3".to_string(),
+                    amount: Uint128::new(300),
+                },
+            ],
+        }),
+    )
+    .unwrap();
+
+    let res: ValidatorsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::Validators {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.validators,
+        vec![
+            ValidatorStake {
+                validator: "val1".to_string(),
+                stake: Uint128::new(100),
+            },
+            ValidatorStake {
+                validator: "val2".to_string(),
+                stake: Uint128::new(200),
+            },
+        ]
+    );
+
+    let res: ValidatorsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::Validators {
+                start_after: Some("val2".to_string()),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.validators,
+        vec![ValidatorStake {
+            validator: "val3".to_string(),
+            stake: Uint128::new(300),
+        }]
+    );
+}
+
+#[test]
+fn test_query_reward_state_and_broken_nfts() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    mint(deps.as_mut(), "3");
+    claim_alliance_emissions(&mut deps, Uint128::new(100));
+    break_nft(deps.as_mut(), "1");
+    break_nft(deps.as_mut(), "3");
+
+    let res: RewardStateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::RewardState {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        RewardStateResponse {
+            reward_index: RewardIndex {
+                index: Decimal256::from_str("33.333333333333333333").unwrap(),
+                remainder: Decimal256::from_str("0.000000000000000001").unwrap(),
+                scale: Decimal256::one(),
+            },
+            num_active_nfts: 1,
+            total_active_weight: 1,
+            asset_rewards: vec![],
+        }
+    );
+
+    let res: BrokenNftsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::BrokenNfts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.tokens, vec!["1".to_string(), "3".to_string()]);
+
+    let res: BrokenNftsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::BrokenNfts {
+                start_after: Some("1".to_string()),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.tokens, vec!["3".to_string()]);
+}
+
+#[test]
+fn test_query_delegation_drift() {
+    let mut deps = mock_dependencies_with_alliance();
+    setup_contract(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![
+                AllianceDelegation {
+                    validator: "val1".to_string(),
+                    amount: Uint128::new(100),
+                },
+                AllianceDelegation {
+                    validator: "val2".to_string(),
+                    amount: Uint128::new(200),
+                },
+            ],
+        }),
+    )
+    .unwrap();
+    deps.querier
+        .set_alliance_delegation("val1", Uint128::new(90));
+
+    let res: DelegationDriftResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::DelegationDrift {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.validators,
+        vec![
+            ValidatorDrift {
+                validator: "val1".to_string(),
+                tracked: Uint128::new(100),
+                actual: Uint128::new(90),
+            },
+            ValidatorDrift {
+                validator: "val2".to_string(),
+                tracked: Uint128::new(200),
+                actual: Uint128::zero(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_query_harvest_history() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    claim_alliance_emissions(&mut deps, Uint128::new(100));
+    claim_alliance_emissions(&mut deps, Uint128::new(300));
+
+    let res: HarvestHistoryResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::HarvestHistory {
+                start_before: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let harvest = |lst_received: u128, index_increment: u128| Harvest {
+        time: mock_env().block.time,
+        luna_bonded: Uint128::zero(),
+        lst_received: Uint128::new(lst_received),
+        treasury_amount: Uint128::zero(),
+        keeper_fee: Uint128::zero(),
+        index_increment: Decimal256::from_ratio(index_increment, 1u128),
+        active_weight: 2,
+    };
+    assert_eq!(
+        res.harvests,
+        vec![(1, harvest(300, 150)), (0, harvest(100, 50))]
+    );
+
+    let res: HarvestHistoryResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::HarvestHistory {
+                start_before: Some(1),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.harvests, vec![(0, harvest(100, 50))]);
+}
+
+#[test]
+fn test_query_reward_rate() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    deps.querier.update_wasm(|_| {
+        SystemResult::Ok(ContractResult::Ok(
+            to_json_binary(&StateResponse {
+                total_ustake: Uint128::new(1_000),
+                total_uluna: Uint128::new(1_200),
+                exchange_rate: Decimal::from_str("1.2").unwrap(),
+                unlocked_coins: vec![],
+                unbonding: Uint128::zero(),
+                available: Uint128::zero(),
+                tvl_uluna: Uint128::new(1_200),
+            })
+            .unwrap(),
+        ))
+    });
+
+    let env = mock_env();
+    for (days_ago, index_increment) in [(100, 10u128), (50, 5), (1, 3)] {
+        let harvest = Harvest {
+            time: env.block.time.minus_days(days_ago),
+            luna_bonded: Uint128::zero(),
+            lst_received: Uint128::zero(),
+            treasury_amount: Uint128::zero(),
+            keeper_fee: Uint128::zero(),
+            index_increment: Decimal256::from_ratio(index_increment, 1u128),
+            active_weight: 1,
+        };
+        add_harvest(deps.as_mut().storage, &harvest).unwrap();
+    }
+
+    // only the harvests of the last 73 days are counted, a fifth of a year
+    let window_seconds = 73 * 24 * 60 * 60;
+    let res: RewardRateResponse = from_json(
+        query(
+            deps.as_ref(),
+            env,
+            QueryCollectionMsg::RewardRate { window_seconds },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        RewardRateResponse {
+            window_seconds,
+            harvests: 2,
+            lst_per_weight: Decimal256::from_ratio(8u128, 1u128),
+            luna_per_weight: Decimal256::from_str("9.6").unwrap(),
+            annual_lst_per_weight: Decimal256::from_ratio(40u128, 1u128),
+            annual_luna_per_weight: Decimal256::from_ratio(48u128, 1u128),
+        }
+    );
+
+    // harvests before a switch of the LST are not counted
+    LST_SWITCH_HARVEST_ID
+        .save(deps.as_mut().storage, &2)
+        .unwrap();
+    let res: RewardRateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::RewardRate { window_seconds },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.harvests, 1);
+    assert_eq!(res.lst_per_weight, Decimal256::from_ratio(3u128, 1u128));
+}
+
+#[test]
+fn test_query_unbond_requests() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    deps.querier.update_wasm(|request| match request {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == "lst_hub" => {
+            let msg: eris::QueryMsg = from_json(msg).unwrap();
+            assert_eq!(
+                msg,
+                eris::QueryMsg::UnbondRequestsByUser {
+                    user: "owner".to_string(),
+                    start_after: None,
+                    limit: Some(10),
+                }
+            );
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&vec![UnbondRequestsByUserResponseItem {
+                    id: 7,
+                    shares: Uint128::new(500),
+                }])
+                .unwrap(),
+            ))
+        }
+        _ => panic!("unexpected query"),
+    });
+
+    let res: UnbondRequestsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::UnbondRequests {
+                user: "owner".to_string(),
+                start_after: None,
+                limit: Some(10),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.requests,
+        vec![UnbondRequestsByUserResponseItem {
+            id: 7,
+            shares: Uint128::new(500),
+        }]
+    );
+}
+
+#[test]
+fn test_query_owner_portfolio() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    mint(deps.as_mut(), "3");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::UpdateConfig(UpdateConfigMsg {
+            reward_assets: Some(vec![AssetInfoUnchecked::native("ustars")]),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+    set_contract_balance(&mut deps, Coin::new(90, "ustars"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteCollectionMsg::StakeRewardsCallback { keeper: None },
+    )
+    .unwrap();
+    claim_alliance_emissions(&mut deps, Uint128::new(900));
+    break_nft(deps.as_mut(), "2");
+    deps.querier.update_wasm(|_| {
+        SystemResult::Ok(ContractResult::Ok(
+            to_json_binary(&StateResponse {
+                total_ustake: Uint128::new(1_000),
+                total_uluna: Uint128::new(1_200),
+                exchange_rate: Decimal::from_str("1.2").unwrap(),
+                unlocked_coins: vec![],
+                unbonding: Uint128::zero(),
+                available: Uint128::zero(),
+                tvl_uluna: Uint128::new(1_200),
+            })
+            .unwrap(),
+        ))
+    });
+
+    let portfolio = |owner: &str, start_after: Option<&str>| -> OwnerPortfolioResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryCollectionMsg::OwnerPortfolio {
+                    owner: owner.to_string(),
+                    start_after: start_after.map(|s| s.to_string()),
+                    limit: Some(2),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let nft = |token_id: &str, broken: bool, rewards: u128, luna_rewards: u128| PortfolioNft {
+        token_id: token_id.to_string(),
+        broken,
+        rewards: Uint128::new(rewards),
+        luna_rewards: Uint128::new(luna_rewards),
+        asset_rewards: if broken {
+            vec![]
+        } else {
+            vec![Asset::native("ustars", 30u128)]
+        },
+    };
+
+    assert_eq!(
+        portfolio("owner", None),
+        OwnerPortfolioResponse {
+            nfts: vec![nft("1", false, 300, 360), nft("2", true, 0, 0)],
+            total_rewards: Uint128::new(300),
+            total_luna_rewards: Uint128::new(360),
+            total_asset_rewards: vec![Asset::native("ustars", 30u128)],
+            exchange_rate: Decimal::from_str("1.2").unwrap(),
+        }
+    );
+    assert_eq!(
+        portfolio("owner", Some("2")).nfts,
+        vec![nft("3", false, 300, 360)]
+    );
+    assert_eq!(portfolio("owner2", None).nfts, vec![]);
+}
+
+#[test]
+fn test_query_simulate_break() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    mint(deps.as_mut(), "3");
+    claim_alliance_emissions(&mut deps, Uint128::new(900));
+    break_nft(deps.as_mut(), "3");
+
+    let simulate = |token_ids: &[&str]| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::SimulateBreak {
+                token_ids: token_ids.iter().map(|id| id.to_string()).collect(),
+            },
+        )
+    };
+    simulate(&[]).unwrap_err();
+    simulate(&["1", "1"]).unwrap_err();
+    simulate(&["3"]).unwrap_err();
+    simulate(&["4"]).unwrap_err();
+
+    let res: SimulateBreakResponse = from_json(simulate(&["1", "2"]).unwrap()).unwrap();
+    let nft = |token_id: &str| SimulatedBreak {
+        token_id: token_id.to_string(),
+        rewards: Uint128::new(300),
+        asset_rewards: vec![],
+    };
+    assert_eq!(
+        res,
+        SimulateBreakResponse {
+            nfts: vec![nft("1"), nft("2")],
+            total_rewards: Uint128::new(600),
+            total_asset_rewards: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_query_simulate_harvest() {
+    let mut deps = mock_dependencies_with_alliance();
+    setup_contract(deps.as_mut());
+    mint(deps.as_mut(), "1");
+    mint(deps.as_mut(), "2");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteCollectionMsg::AllianceDelegate(AllianceDelegateMsg {
+            delegations: vec![
+                AllianceDelegation {
+                    validator: "val1".to_string(),
+                    amount: Uint128::new(100),
+                },
+                AllianceDelegation {
+                    validator: "val2".to_string(),
+                    amount: Uint128::new(200),
+                },
+            ],
+        }),
+    )
+    .unwrap();
+    CONFIG
+        .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+            cfg.dao_treasury_share = Decimal::percent(10);
+            cfg.keeper_fee = Decimal::percent(1);
+            cfg.harvest_interval = 3_600;
+            Ok(cfg)
+        })
+        .unwrap();
+
+    // val2 has no rewards, the LUNA held by the contract is bonded as well
+    deps.querier.set_alliance_rewards("val1", Uint128::new(900));
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(100, "uluna")]);
+    deps.querier.update_wasm(|_| {
+        SystemResult::Ok(ContractResult::Ok(
+            to_json_binary(&StateResponse {
+                total_ustake: Uint128::new(1_000),
+                total_uluna: Uint128::new(1_200),
+                exchange_rate: Decimal::from_str("1.2").unwrap(),
+                unlocked_coins: vec![],
+                unbonding: Uint128::zero(),
+                available: Uint128::zero(),
+                tvl_uluna: Uint128::new(1_200),
+            })
+            .unwrap(),
+        ))
+    });
+
+    let res: SimulateHarvestResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryCollectionMsg::SimulateHarvest {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateHarvestResponse {
+            pending_rewards: Uint128::new(900),
+            luna_to_bond: Uint128::new(1_000),
+            exchange_rate: Decimal::from_str("1.2").unwrap(),
+            expected_lst: Uint128::new(833),
+            treasury_amount: Uint128::new(83),
+            keeper_fee: Uint128::new(8),
+            index_increment: Decimal256::from_ratio(371u128, 1u128),
+        }
+    );
+
+    // no harvest is simulated before the harvest interval has passed
+    let mut env = mock_env();
+    LAST_HARVEST
+        .save(deps.as_mut().storage, &env.block.time)
+        .unwrap();
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryCollectionMsg::SimulateHarvest {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        StdError::generic_err(
+            ContractError::HarvestTooEarly(env.block.time.plus_seconds(3_600)).to_string()
+        )
+        .to_string()
+    );
+    env.block.time = env.block.time.plus_seconds(3_600);
+    query(
+        deps.as_ref(),
+        env.clone(),
+        QueryCollectionMsg::SimulateHarvest {},
+    )
+    .unwrap();
+
+    // a failing rewards query is not read as no rewards
+    deps.querier.set_alliance_query_error("val1");
+    query(deps.as_ref(), env, QueryCollectionMsg::SimulateHarvest {}).unwrap_err();
+}
@@ -1,4 +1,6 @@
-use alliance_nft_packages::{instantiate::InstantiateMinterMsg, execute::ExecuteMinterMsg, query::QueryMinterMsg};
+use alliance_nft_packages::{
+    execute::ExecuteMinterMsg, instantiate::InstantiateMinterMsg, query::QueryMinterMsg,
+};
 use cosmwasm_schema::write_api;
 
 fn main() {
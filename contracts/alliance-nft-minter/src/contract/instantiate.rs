@@ -3,6 +3,7 @@ use crate::state::{CONFIG, STATS};
 use alliance_nft_packages::{
     errors::ContractError,
     instantiate::{InstantiateCollectionMsg, InstantiateMinterMsg},
+    lst::LstProvider,
     state::{MinterConfig, MinterStats},
 };
 use cosmwasm_std::{
@@ -65,6 +66,7 @@ pub fn instantiate(
             lst_hub_address: msg.lst_hub_address,
             dao_treasury_share: msg.dao_treasury_share,
             lst_asset_info: msg.lst_asset_info,
+            lst_provider: LstProvider::Eris,
         })?,
         funds: info.funds,
         label: "Alliance NFT Collection".to_string(),
@@ -95,7 +97,7 @@ pub fn reply_on_instantiate(deps: DepsMut, reply: Reply) -> Result<Response, Con
         .ok_or_else(|| StdError::generic_err("cannot find `_contract_address` attribute"))?
         .value;
 
-    let contract_addr = deps.api.addr_validate(&contract_addr)?;
+    let contract_addr = deps.api.addr_validate(contract_addr)?;
     CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
         config.nft_collection_address = Some(contract_addr);
         Ok(config)
@@ -3,16 +3,18 @@ use crate::contract::query::query;
 use crate::contract::reply::reply;
 
 use alliance_nft_packages::instantiate::{InstantiateCollectionMsg, InstantiateMinterMsg};
+use alliance_nft_packages::lst::LstProvider;
 use alliance_nft_packages::query::QueryMinterMsg;
 use alliance_nft_packages::state::MinterConfig;
 use cosmwasm_std::testing::{
     mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
 };
 use cosmwasm_std::{
-    to_binary, Addr, Empty, Env, Event, MessageInfo, OwnedDeps, Reply, Response, SubMsg,
-    SubMsgResponse, SubMsgResult, Timestamp, WasmMsg,
+    to_json_binary, Addr, Decimal, Empty, Env, Event, MessageInfo, OwnedDeps, Reply, Response,
+    SubMsg, SubMsgResponse, SubMsgResult, Timestamp, WasmMsg,
 };
 use cw2::get_contract_version;
+use cw_asset::AssetInfoUnchecked;
 
 #[test]
 fn test_instantiate() {
@@ -22,13 +24,15 @@ fn test_instantiate() {
 
     assert_eq!(
         res.to_string(),
-        to_binary(&MinterConfig {
+        to_json_binary(&MinterConfig {
             dao_treasury_address: Some(Addr::unchecked("dao_treasury_address")),
             nft_collection_address: Some(Addr::unchecked("nft_collection_address")),
             owner: Addr::unchecked("creator"),
             mint_start_time: Timestamp::from_seconds(1),
             mint_end_time: Timestamp::from_seconds(3),
-        }).unwrap().to_string()
+        })
+        .unwrap()
+        .to_string()
     );
 }
 
@@ -46,12 +50,15 @@ fn test_instantiate_wrong_time_range() {
         nft_collection_code_id: 1,
         mint_start_time: Timestamp::from_seconds(3),
         mint_end_time: Timestamp::from_seconds(1),
+        dao_treasury_share: Decimal::percent(10),
+        lst_hub_address: String::from("lst_hub"),
+        lst_asset_info: AssetInfoUnchecked::cw20("lst_token"),
     };
     let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg);
 
     // assert the message error
     assert_eq!(
-        res.unwrap_err().to_string(), 
+        res.unwrap_err().to_string(),
         String::from("Invalid mint time range, mint_start_time is greater than mint_end_time")
     );
 }
@@ -73,6 +80,9 @@ pub fn intantiate_with_reply() -> (
         nft_collection_code_id: 1,
         mint_start_time: Timestamp::from_seconds(1),
         mint_end_time: Timestamp::from_seconds(3),
+        dao_treasury_share: Decimal::percent(10),
+        lst_hub_address: String::from("lst_hub"),
+        lst_asset_info: AssetInfoUnchecked::cw20("lst_token"),
     };
     let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -86,11 +96,16 @@ pub fn intantiate_with_reply() -> (
         WasmMsg::Instantiate {
             admin: Some(env.contract.address.to_string()),
             code_id: 1,
-            msg: to_binary(&InstantiateCollectionMsg {
+            msg: to_json_binary(&InstantiateCollectionMsg {
                 name: "AllianceNFT".to_string(),
                 symbol: "ALLIANCE".to_string(),
                 minter: env.contract.address.to_string(),
                 owner: Addr::unchecked("cosmos2contract"),
+                dao_treasury_address: "dao_treasury_address".to_string(),
+                dao_treasury_share: Decimal::percent(10),
+                lst_hub_address: "lst_hub".to_string(),
+                lst_asset_info: AssetInfoUnchecked::cw20("lst_token"),
+                lst_provider: LstProvider::Eris,
             })
             .unwrap(),
             funds: vec![],
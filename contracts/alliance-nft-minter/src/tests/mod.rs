@@ -1,4 +1,4 @@
-mod instantiate;
 mod execute;
+mod helpers;
+mod instantiate;
 mod query;
-mod helpers;
\ No newline at end of file
@@ -65,7 +65,7 @@ const useAllianceContracts: (address?: string) => IUseContracts = (
         address,
         contractAddresses.collection,
         {
-          break_nft: token_id,
+          break_nft: { token_id },
         }
       )
 
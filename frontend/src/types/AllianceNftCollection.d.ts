@@ -31,7 +31,16 @@ export type ExecuteMsg =
       change_owner: string;
     }
   | {
-      break_nft: string;
+      break_nft: {
+        payout?: BreakPayout;
+        token_id: string;
+      };
+    }
+  | {
+      break_nfts: {
+        payout?: BreakPayout;
+        token_ids: string[];
+      };
     }
   | {
       mint: MintMsg;
@@ -73,6 +82,7 @@ export type ExecuteMsg =
         operator: string;
       };
     };
+export type BreakPayout = "lst" | "unbond";
 export type Uint128 = string;
 export type Binary = string;
 export type Expiration =
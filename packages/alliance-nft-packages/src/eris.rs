@@ -14,15 +14,46 @@ pub fn validate_dao_treasury_share(share: Decimal) -> Result<Decimal, ContractEr
     }
 }
 
+pub fn validate_keeper_fee(fee: Decimal) -> Result<Decimal, ContractError> {
+    if fee > Decimal::from_ratio(5u128, 100u128) {
+        Err(ContractError::InvalidKeeperFee {})
+    } else {
+        Ok(fee)
+    }
+}
+
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Bond specified amount of Luna
     Bond { receiver: Option<String> },
+    /// Queue the sent ampLUNA (native) for unbonding in the current batch
+    QueueUnbond { receiver: Option<String> },
+    /// Withdraw the Luna of the unbonding requests that have finished
+    WithdrawUnbonded { receiver: Option<String> },
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Queue the sent ampLUNA (cw20) for unbonding in the current batch
+    QueueUnbond { receiver: Option<String> },
 }
 
 #[cw_serde]
 pub enum QueryMsg {
     State {},
+    UnbondRequestsByUser {
+        user: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct UnbondRequestsByUserResponseItem {
+    /// ID of the batch
+    pub id: u64,
+    /// The user's share in the batch
+    pub shares: Uint128,
 }
 
 #[cw_serde]
@@ -1,4 +1,4 @@
-use cosmwasm_std::{Addr, OverflowError, StdError, Timestamp};
+use cosmwasm_std::{Addr, OverflowError, StdError, Timestamp, Uint128};
 use cw721_base::ContractError as CW721BaseError;
 use cw_asset::AssetError;
 use thiserror::Error;
@@ -23,9 +23,18 @@ pub enum ContractError {
     #[error("Invalid reply id {0}")]
     InvalidReplyId(u64),
 
+    #[error("Amount of LST minted not found in the reply of the LST hub")]
+    BondedAmountNotFound {},
+
     #[error("Empty delegation")]
     EmptyDelegation {},
 
+    #[error("Validator targets must be set")]
+    ValidatorTargetsNotSet {},
+
+    #[error("Invalid target weight for validator {0}")]
+    InvalidValidatorTarget(String),
+
     #[error("Validator {0} not found")]
     ValidatorNotFound(String),
 
@@ -35,8 +44,32 @@ pub enum ContractError {
     #[error("NFT already broken")]
     AlreadyBroken {},
 
-    #[error("No active NFTs")]
-    NoActiveNfts {},
+    #[error("NFT is not broken")]
+    NotBroken {},
+
+    #[error("Repairing NFTs is disabled, the repair fee is not set")]
+    RepairDisabled {},
+
+    #[error("Invalid repair fee {0}")]
+    InvalidRepairFee(String),
+
+    #[error("Forging NFTs is disabled, the number of forge inputs is not set")]
+    ForgeDisabled {},
+
+    #[error("Forging requires {0} broken NFTs")]
+    InvalidForgeInputs(u32),
+
+    #[error("No forge metadata available")]
+    NoForgeMetadata {},
+
+    #[error("Invalid funds, expected {0}")]
+    InvalidFunds(String),
+
+    #[error("Duplicate token id {0}")]
+    DuplicateTokenId(String),
+
+    #[error("No token ids provided")]
+    EmptyTokenIds {},
 
     #[error("Invalid mint time range, mint_start_time is greater than mint_end_time")]
     InvalidMintTimeRange {},
@@ -47,6 +80,27 @@ pub enum ContractError {
     #[error("Invalid DAO treasury share. Must be less than or equal 20%")]
     InvalidDaoTreasuryShare {},
 
+    #[error("Invalid keeper fee. Must be less than or equal 5%")]
+    InvalidKeeperFee {},
+
+    #[error("Rewards were harvested recently, next harvest is possible at {0}")]
+    HarvestTooEarly(Timestamp),
+
+    #[error("Invalid reward asset {0}")]
+    InvalidRewardAsset(String),
+
+    #[error("Invalid weight for NFT {0}, must be a positive integer")]
+    InvalidNftWeight(String),
+
+    #[error("Invalid LST {0}, must differ from the current LST and the reward assets")]
+    InvalidLst(String),
+
+    #[error("The swap must convert all of the previous LST, {0} left")]
+    LstSwitchIncomplete(Uint128),
+
+    #[error("Received {0} of the new LST, less than the minimum of {1}")]
+    LstSwitchMinReceived(Uint128, Uint128),
+
     #[error("Minting period starts at {0} and ends at {1}. Current time is {2}")]
     OutOfMintingPeriod(Timestamp, Timestamp, Timestamp),
 
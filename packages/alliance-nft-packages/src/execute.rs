@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Binary, Decimal, Empty, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Empty, Uint128};
 use cw721_base::ExecuteMsg as CW721ExecuteMsg;
+use cw_asset::{AssetInfoUnchecked, AssetUnchecked};
 use cw_utils::Expiration;
 
+use crate::lst::LstProvider;
 use crate::state::MinterExtension;
 
 use super::Extension;
@@ -21,6 +23,8 @@ pub struct MintMsg {
     pub token_uri: Option<String>,
     /// Any custom extension used by this contract
     pub extension: Extension,
+    /// Reward weight of the NFT, derived from the weight trait if not set
+    pub weight: Option<u64>,
 }
 
 #[cw_serde]
@@ -29,18 +33,79 @@ pub enum ExecuteCollectionMsg {
     AllianceDelegate(AllianceDelegateMsg),
     AllianceUndelegate(AllianceUndelegateMsg),
     AllianceRedelegate(AllianceRedelegateMsg),
+    // Redelegate and delegate the virtual tokens to reach the validator targets of the config
+    Rebalance {},
     AllianceClaimRewards {},
-    StakeRewardsCallback {},
-    UpdateRewardsCallback(UpdateRewardsCallbackMsg),
+    StakeRewardsCallback {
+        /// Caller of AllianceClaimRewards receiving the keeper fee
+        keeper: Option<Addr>,
+    },
     ChangeOwner(String),
     UpdateConfig(UpdateConfigMsg),
+    // Swap the held LST into another LST and rescale the rewards of the NFTs by the amount received
+    SwitchLst(SwitchLstMsg),
+    // Send the rewards that are not distributed to any NFT to the DAO treasury
+    SweepUndistributedRewards {},
+    // Update the tracked stake of each validator to the
+    // balance delegated in the Alliance module
+    SyncDelegations {},
 
     // Claim the accumulated rewards and send them to the owner
     // while the NFT is broken it will not accumulate rewards
-    BreakNft(String),
+    BreakNft {
+        token_id: String,
+        #[serde(default)]
+        payout: BreakPayout,
+    },
+    // Break multiple NFTs at once, sending the sum of their rewards in a single transfer
+    BreakNfts {
+        token_ids: Vec<String>,
+        #[serde(default)]
+        payout: BreakPayout,
+    },
+
+    // Claim the accumulated rewards and send them to the owner
+    // without breaking the NFTs, so they keep accumulating rewards
+    ClaimRewards {
+        token_ids: Vec<String>,
+    },
+
+    // Pay the repair fee to the DAO treasury to reactivate a broken NFT,
+    // which accumulates rewards again from the current reward index
+    RepairNft {
+        token_id: String,
+    },
+
+    // Burn broken NFTs of the sender and mint a new NFT from the forge metadata
+    Forge {
+        token_ids: Vec<String>,
+    },
+    // Add metadata to the pool that forged NFTs are minted from
+    AppendForgeMetadata(Vec<MinterExtension>),
+    // Remove metadata from the pool by token_id
+    RemoveForgeMetadata {
+        token_ids: Vec<String>,
+    },
+
+    // Derive the reward weight of NFTs minted before the weight trait was
+    // configured, keeping the rewards they accrued so far
+    RefreshNftWeights {
+        token_ids: Vec<String>,
+    },
+
+    // Set the address receiving the rewards of the NFT instead of its owner,
+    // removed when the NFT is transferred. None pays the rewards to the owner again
+    SetRewardRecipient {
+        token_id: String,
+        recipient: Option<String>,
+    },
 
     /// Mint a new NFT, can only be called by the contract minter
     Mint(MintMsg),
+    /// Burn the NFT, paying out the rewards it accrued if it is not broken
+    Burn {
+        token_id: String,
+    },
 
     /// CW721 standard message
 
@@ -115,19 +180,59 @@ impl From<ExecuteCollectionMsg> for CW721ExecuteMsg<Extension, Empty> {
                 CW721ExecuteMsg::ApproveAll { operator, expires }
             }
             ExecuteCollectionMsg::RevokeAll { operator } => CW721ExecuteMsg::RevokeAll { operator },
+            ExecuteCollectionMsg::Burn { token_id } => CW721ExecuteMsg::Burn { token_id },
             _ => panic!("cannot covert {:?} to CW721ExecuteMsg", msg),
         }
     }
 }
 
 #[cw_serde]
-pub struct UpdateRewardsCallbackMsg {
-    pub previous_lst_balance: Uint128,
+#[derive(Default)]
+pub struct UpdateConfigMsg {
+    pub dao_treasury_share: Option<Decimal>,
+    /// Replaces the list of additional reward assets accounted per NFT
+    pub reward_assets: Option<Vec<AssetInfoUnchecked>>,
+    /// Trait used to derive the reward weight of the NFTs, an empty string clears it
+    pub weight_trait: Option<String>,
+    /// Unbonding period of the Alliance module in seconds
+    pub unbonding_period: Option<u64>,
+    /// Replaces the target weight of the stake delegated to each validator
+    pub validator_targets: Option<BTreeMap<String, u64>>,
+    /// Share of the harvested rewards paid to the caller of AllianceClaimRewards
+    pub keeper_fee: Option<Decimal>,
+    /// Minimum number of seconds between two harvests
+    pub harvest_interval: Option<u64>,
+    /// Pay out the pending rewards to the previous owner when an NFT is transferred
+    pub settle_rewards_on_transfer: Option<bool>,
+    /// Fee paid to the DAO treasury to repair a broken NFT (native or cw20)
+    pub repair_fee: Option<AssetUnchecked>,
+    /// Number of broken NFTs burned to forge a new NFT
+    pub forge_inputs: Option<u32>,
 }
 
 #[cw_serde]
-pub struct UpdateConfigMsg {
-    pub dao_treasury_share: Option<Decimal>,
+#[derive(Default)]
+pub enum BreakPayout {
+    /// Transfer the rewards in the LST
+    #[default]
+    Lst,
+    /// Queue the rewards for unbonding on the LST hub on behalf of the owner,
+    /// who withdraws the LUNA from the hub after the unbonding period
+    Unbond,
+}
+
+#[cw_serde]
+pub struct SwitchLstMsg {
+    /// Contract of the hub of the new LST
+    pub lst_hub_address: String,
+    pub lst_asset_info: AssetInfoUnchecked,
+    pub lst_provider: LstProvider,
+    /// Contract converting the held LST into the new LST (e.g. a pair or a router),
+    /// executed with all held LST as funds or through a cw20 Send message
+    pub swap_contract: String,
+    pub swap_msg: Binary,
+    /// Minimum amount of the new LST that must be received
+    pub min_received: Uint128,
 }
 
 #[cw_serde]
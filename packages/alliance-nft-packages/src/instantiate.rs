@@ -3,6 +3,8 @@ use cosmwasm_std::{Addr, Decimal, Timestamp};
 use cw721_base::InstantiateMsg as CW721InstantiateMsg;
 use cw_asset::AssetInfoUnchecked;
 
+use crate::lst::LstProvider;
+
 #[cw_serde]
 pub struct InstantiateCollectionMsg {
     pub name: String,
@@ -14,6 +16,9 @@ pub struct InstantiateCollectionMsg {
     pub dao_treasury_share: Decimal,
     pub lst_hub_address: String,
     pub lst_asset_info: AssetInfoUnchecked,
+    /// Interface of the LST hub, ERIS by default
+    #[serde(default)]
+    pub lst_provider: LstProvider,
 }
 
 impl From<InstantiateCollectionMsg> for CW721InstantiateMsg {
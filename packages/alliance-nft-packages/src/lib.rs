@@ -2,6 +2,7 @@ pub mod eris;
 pub mod errors;
 pub mod execute;
 pub mod instantiate;
+pub mod lst;
 pub mod migrate;
 pub mod query;
 pub mod state;
@@ -0,0 +1,167 @@
+use crate::eris::{self, Hub, UnbondRequestsByUserResponseItem};
+use crate::errors::ContractError;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, to_json_binary, CosmosMsg, Decimal, Event, QuerierWrapper, StdResult, Uint128, WasmMsg,
+};
+use cw_asset::{Asset, AssetInfo};
+
+/// Interface of the liquid staking hub that the LUNA rewards are bonded with
+#[cw_serde]
+#[derive(Default)]
+pub enum LstProvider {
+    /// ERIS Amplifier (ampLUNA)
+    #[default]
+    Eris,
+    /// Hubs based on Steak (e.g. Backbone Labs boneLUNA)
+    Steak,
+}
+
+pub mod steak {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Coin, Decimal, Uint128};
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        /// Bond specified amount of Luna
+        Bond { receiver: Option<String> },
+        /// Withdraw the Luna of the unbonding requests that have finished
+        WithdrawUnbonded { receiver: Option<String> },
+    }
+
+    #[cw_serde]
+    pub enum ReceiveMsg {
+        /// Queue the sent Steak token for unbonding in the current batch
+        QueueUnbond { receiver: Option<String> },
+    }
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        State {},
+        UnbondRequestsByUser {
+            user: String,
+            start_after: Option<u64>,
+            limit: Option<u32>,
+        },
+    }
+
+    #[cw_serde]
+    pub struct StateResponse {
+        /// Total supply to the Steak token
+        pub total_usteak: Uint128,
+        /// Total amount of uluna staked
+        pub total_uluna: Uint128,
+        /// The exchange rate between usteak and uluna, in terms of uluna per usteak
+        pub exchange_rate: Decimal,
+        /// Staking rewards currently held by the contract that are ready to be reinvested
+        pub unlocked_coins: Vec<Coin>,
+    }
+}
+
+impl LstProvider {
+    /// executes a bond message to the staking hub, minting the LST to the sender
+    pub fn bond_msg(
+        &self,
+        hub: &Hub,
+        denom: impl Into<String>,
+        amount: u128,
+    ) -> StdResult<CosmosMsg> {
+        let msg = match self {
+            LstProvider::Eris => to_json_binary(&eris::ExecuteMsg::Bond { receiver: None })?,
+            LstProvider::Steak => to_json_binary(&steak::ExecuteMsg::Bond { receiver: None })?,
+        };
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub.0.to_string(),
+            msg,
+            funds: vec![coin(amount, denom)],
+        }))
+    }
+
+    /// queries the uluna per LST of the staking hub
+    pub fn query_exchange_rate(&self, querier: &QuerierWrapper, hub: &Hub) -> StdResult<Decimal> {
+        match self {
+            LstProvider::Eris => Ok(hub.query_state(querier)?.exchange_rate),
+            LstProvider::Steak => {
+                let state: steak::StateResponse =
+                    querier.query_wasm_smart(hub.0.to_string(), &steak::QueryMsg::State {})?;
+                Ok(state.exchange_rate)
+            }
+        }
+    }
+
+    /// queues the LST for unbonding on the staking hub, the receiver withdraws the unbonded
+    /// LUNA from the hub once the unbonding period has passed
+    pub fn queue_unbond_msg(
+        &self,
+        hub: &Hub,
+        lst: Asset,
+        receiver: String,
+    ) -> Result<CosmosMsg, ContractError> {
+        let receiver = Some(receiver);
+        match (self, &lst.info) {
+            (LstProvider::Eris, AssetInfo::Native(denom)) => {
+                Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: hub.0.to_string(),
+                    msg: to_json_binary(&eris::ExecuteMsg::QueueUnbond { receiver })?,
+                    funds: vec![coin(lst.amount.u128(), denom)],
+                }))
+            }
+            (LstProvider::Eris, _) => Ok(lst.send_msg(
+                hub.0.to_string(),
+                to_json_binary(&eris::ReceiveMsg::QueueUnbond { receiver })?,
+            )?),
+            (LstProvider::Steak, _) => Ok(lst.send_msg(
+                hub.0.to_string(),
+                to_json_binary(&steak::ReceiveMsg::QueueUnbond { receiver })?,
+            )?),
+        }
+    }
+
+    /// queries the unbonding requests of the user on the staking hub
+    pub fn query_unbond_requests(
+        &self,
+        querier: &QuerierWrapper,
+        hub: &Hub,
+        user: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<UnbondRequestsByUserResponseItem>> {
+        match self {
+            LstProvider::Eris => querier.query_wasm_smart(
+                hub.0.to_string(),
+                &eris::QueryMsg::UnbondRequestsByUser {
+                    user,
+                    start_after,
+                    limit,
+                },
+            ),
+            LstProvider::Steak => querier.query_wasm_smart(
+                hub.0.to_string(),
+                &steak::QueryMsg::UnbondRequestsByUser {
+                    user,
+                    start_after,
+                    limit,
+                },
+            ),
+        }
+    }
+
+    /// parses the amount of LST minted by the staking hub from the events of a bond message
+    pub fn parse_bonded_amount(&self, events: &[Event]) -> Result<Uint128, ContractError> {
+        let (event_type, key) = match self {
+            LstProvider::Eris => ("wasm-erishub/bonded", "ustake_minted"),
+            LstProvider::Steak => ("wasm-steakhub/bonded", "usteak_minted"),
+        };
+        events
+            .iter()
+            .filter(|event| event.ty == event_type)
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == key)
+            .ok_or(ContractError::BondedAmountNotFound {})
+            .and_then(|attr| {
+                attr.value
+                    .parse::<Uint128>()
+                    .map_err(|_| ContractError::BondedAmountNotFound {})
+            })
+    }
+}
@@ -1,12 +1,17 @@
 use super::Extension;
-use crate::state::{Config as ConfigRes, MinterConfig, MinterStats, MinterExtension};
+use crate::eris::UnbondRequestsByUserResponseItem;
+use crate::state::{
+    Config as ConfigRes, Harvest, MinterConfig, MinterExtension, MinterStats, RewardIndex,
+    Unbonding,
+};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Empty;
+use cosmwasm_std::{Addr, Decimal, Decimal256, Empty, Uint128};
 use cw721::{
     AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, ContractInfoResponse, NftInfoResponse,
     NumTokensResponse, OperatorsResponse, OwnerOfResponse, TokensResponse,
 };
 use cw721_base::QueryMsg as CW721QueryMsg;
+use cw_asset::{Asset, AssetInfo};
 
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -125,6 +130,82 @@ pub enum QueryCollectionMsg {
     // Return the minter
     #[returns(MinterResponse)]
     Minter {},
+
+    // Return the reward weight of the NFT
+    #[returns(NftWeightResponse)]
+    NftWeight { token_id: String },
+
+    // Return the address receiving the rewards of the NFT
+    #[returns(RewardRecipientResponse)]
+    RewardRecipient { token_id: String },
+
+    // Return the NFTs of the owner with their broken flag and claimable rewards
+    #[returns(OwnerPortfolioResponse)]
+    OwnerPortfolio {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Return the rewards that breaking the NFTs would pay out
+    #[returns(SimulateBreakResponse)]
+    SimulateBreak { token_ids: Vec<String> },
+
+    // Return the rewards pending in the Alliance module and the LST
+    // that the next harvest would distribute to the NFTs
+    #[returns(SimulateHarvestResponse)]
+    SimulateHarvest {},
+
+    // Return the stake delegated to each validator
+    #[returns(ValidatorsResponse)]
+    Validators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Return the stake tracked for each validator next to the
+    // balance delegated in the Alliance module
+    #[returns(DelegationDriftResponse)]
+    DelegationDrift {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Return the harvests, starting with the most recent one
+    #[returns(HarvestHistoryResponse)]
+    HarvestHistory {
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Return the rewards of an NFT with a weight of 1 over the harvests
+    // of the last window_seconds, together with their annualised amount
+    #[returns(RewardRateResponse)]
+    RewardRate { window_seconds: u64 },
+
+    // Return the undelegations that did not complete yet
+    #[returns(UnbondingsResponse)]
+    Unbondings {},
+
+    // Return the unbonding requests of the user on the LST hub,
+    // e.g. queued when breaking an NFT with the unbond payout
+    #[returns(UnbondRequestsResponse)]
+    UnbondRequests {
+        user: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Return the reward indexes and the active NFTs sharing the rewards
+    #[returns(RewardStateResponse)]
+    RewardState {},
+
+    // Return the ids of the broken NFTs
+    #[returns(BrokenNftsResponse)]
+    BrokenNfts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -132,6 +213,158 @@ pub struct MinterResponse {
     pub minter: String,
 }
 
+#[cw_serde]
+pub struct ValidatorStake {
+    pub validator: String,
+    pub stake: Uint128,
+}
+
+#[cw_serde]
+pub struct ValidatorsResponse {
+    pub validators: Vec<ValidatorStake>,
+}
+
+#[cw_serde]
+pub struct ValidatorDrift {
+    pub validator: String,
+    /// Stake tracked by the contract
+    pub tracked: Uint128,
+    /// Balance delegated in the Alliance module
+    pub actual: Uint128,
+}
+
+#[cw_serde]
+pub struct DelegationDriftResponse {
+    pub validators: Vec<ValidatorDrift>,
+}
+
+#[cw_serde]
+pub struct HarvestHistoryResponse {
+    /// Harvests with their sequence number
+    pub harvests: Vec<(u64, Harvest)>,
+}
+
+#[cw_serde]
+pub struct RewardRateResponse {
+    pub window_seconds: u64,
+    /// Number of harvests within the window
+    pub harvests: u64,
+    /// LST received per unit of NFT weight within the window
+    pub lst_per_weight: Decimal256,
+    /// LUNA value of lst_per_weight at the current exchange rate
+    pub luna_per_weight: Decimal256,
+    /// LST received per unit of NFT weight in a year at the rate of the window
+    pub annual_lst_per_weight: Decimal256,
+    /// LUNA value of annual_lst_per_weight at the current exchange rate
+    pub annual_luna_per_weight: Decimal256,
+}
+
+#[cw_serde]
+pub struct UnbondingsResponse {
+    pub unbondings: Vec<Unbonding>,
+}
+
+#[cw_serde]
+pub struct UnbondRequestsResponse {
+    pub requests: Vec<UnbondRequestsByUserResponseItem>,
+}
+
+#[cw_serde]
+pub struct AssetRewardState {
+    pub asset_info: AssetInfo,
+    pub reward_index: RewardIndex,
+    /// Amount of the asset held by the contract for the NFTs
+    pub held: Uint128,
+}
+
+#[cw_serde]
+pub struct RewardStateResponse {
+    /// Reward index of the LST
+    pub reward_index: RewardIndex,
+    pub num_active_nfts: u64,
+    /// Sum of the weights of all active NFTs
+    pub total_active_weight: u64,
+    /// Reward indexes of the additional reward assets
+    pub asset_rewards: Vec<AssetRewardState>,
+}
+
+#[cw_serde]
+pub struct BrokenNftsResponse {
+    pub tokens: Vec<String>,
+}
+
+#[cw_serde]
+pub struct RewardRecipientResponse {
+    /// Recipient set for the NFT, or the owner if there is none
+    pub recipient: Addr,
+}
+
+#[cw_serde]
+pub struct PortfolioNft {
+    pub token_id: String,
+    pub broken: bool,
+    /// LST claimable by the NFT
+    pub rewards: Uint128,
+    /// LUNA value of the claimable LST at the current exchange rate
+    pub luna_rewards: Uint128,
+    /// Additional reward assets claimable by the NFT
+    pub asset_rewards: Vec<Asset>,
+}
+
+#[cw_serde]
+pub struct OwnerPortfolioResponse {
+    pub nfts: Vec<PortfolioNft>,
+    /// LST claimable by the NFTs of this page
+    pub total_rewards: Uint128,
+    /// LUNA value of the LST claimable by the NFTs of this page
+    pub total_luna_rewards: Uint128,
+    /// Additional reward assets claimable by the NFTs of this page
+    pub total_asset_rewards: Vec<Asset>,
+    /// Exchange rate of the LST hub in LUNA per LST
+    pub exchange_rate: Decimal,
+}
+
+#[cw_serde]
+pub struct SimulatedBreak {
+    pub token_id: String,
+    /// LST paid out on break
+    pub rewards: Uint128,
+    /// Additional reward assets paid out on break
+    pub asset_rewards: Vec<Asset>,
+}
+
+#[cw_serde]
+pub struct SimulateBreakResponse {
+    pub nfts: Vec<SimulatedBreak>,
+    pub total_rewards: Uint128,
+    pub total_asset_rewards: Vec<Asset>,
+}
+
+#[cw_serde]
+pub struct SimulateHarvestResponse {
+    /// LUNA rewards pending in the Alliance module over all validators
+    pub pending_rewards: Uint128,
+    /// LUNA bonded for the LST, the pending rewards and the LUNA held by the contract
+    pub luna_to_bond: Uint128,
+    /// Exchange rate of the LST hub in LUNA per LST
+    pub exchange_rate: Decimal,
+    /// LST expected from bonding at the current exchange rate
+    pub expected_lst: Uint128,
+    /// LST sent to the DAO treasury
+    pub treasury_amount: Uint128,
+    /// LST paid to the caller of AllianceClaimRewards
+    pub keeper_fee: Uint128,
+    /// LST received by an NFT with a weight of 1
+    pub index_increment: Decimal256,
+}
+
+#[cw_serde]
+pub struct NftWeightResponse {
+    pub weight: u64,
+    /// Sum of the weights of all active NFTs
+    pub total_active_weight: u64,
+}
+
 impl From<QueryCollectionMsg> for CW721QueryMsg<Empty> {
     fn from(msg: QueryCollectionMsg) -> CW721QueryMsg<Empty> {
         match msg {
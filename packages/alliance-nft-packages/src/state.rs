@@ -1,18 +1,25 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, Response, Timestamp};
-use cw_asset::AssetInfo;
+use cosmwasm_std::{
+    Addr, Decimal, Decimal256, Response, StdError, StdResult, Timestamp, Uint128, Uint256,
+};
+use cw_asset::{Asset, AssetInfo};
+use std::collections::BTreeMap;
 
-use crate::{eris::Hub, errors::ContractError, Extension};
+use crate::{eris::Hub, errors::ContractError, lst::LstProvider, Extension};
 
 // The NFT collection may be able to accrual rewards
 // in different tokens if the take rate of an Alliance
-// is positive. But for now, breaking an NFT will allow
-// claiming and accounting rewards only for Luna Tokens.
+// is positive. Luna Tokens are staked in the LST, while
+// the other reward assets listed in the config are
+// accounted and paid out to the NFTs as they are.
 //
-// Whatsoever, the DAO will be able to use these other
-// rewards to do anything they want collectively
+// Rewards in assets that are not listed remain in the
+// contract and the DAO will be able to use them collectively
 pub const ALLOWED_DENOM: &str = "uluna";
 
+// Unbonding period of the Alliance module on Terra (21 days)
+pub const DEFAULT_UNBONDING_PERIOD: u64 = 21 * 24 * 60 * 60;
+
 #[cw_serde]
 pub struct Trait {
     pub display_type: Option<String>,
@@ -34,6 +41,9 @@ pub struct Metadata {
     pub youtube_url: Option<String>,
 }
 
+// Trait of forged NFTs listing the token ids of the NFTs burned to forge them
+pub const FORGED_TRAIT: &str = "Forged";
+
 #[cw_serde]
 pub struct Config {
     pub owner: Addr,
@@ -50,6 +60,228 @@ pub struct Config {
     pub lst_hub: Hub,
     /// Contract of CW20 ampLUNA
     pub lst_asset_info: AssetInfo,
+    /// Interface of the LST hub
+    #[serde(default)]
+    pub lst_provider: LstProvider,
+
+    /// Additional reward assets (native or cw20) accounted per NFT
+    #[serde(default)]
+    pub reward_assets: Vec<AssetInfo>,
+    /// Trait whose value is used as the reward weight of the NFTs (e.g. rarity),
+    /// NFTs without the trait have a weight of 1
+    #[serde(default)]
+    pub weight_trait: Option<String>,
+
+    /// Unbonding period of the Alliance module in seconds
+    #[serde(default = "default_unbonding_period")]
+    pub unbonding_period: u64,
+
+    /// Target weight of the stake delegated to each validator, used by Rebalance
+    #[serde(default)]
+    pub validator_targets: BTreeMap<String, u64>,
+
+    /// Specifies how much of the harvested rewards are paid to the caller of AllianceClaimRewards
+    #[serde(default)]
+    pub keeper_fee: Decimal,
+    /// Minimum number of seconds between two harvests
+    #[serde(default)]
+    pub harvest_interval: u64,
+
+    /// Pays out the pending rewards of an NFT to the previous owner when it is transferred
+    #[serde(default)]
+    pub settle_rewards_on_transfer: bool,
+
+    /// Fee paid to the DAO treasury to repair a broken NFT, repairing is disabled while not set
+    #[serde(default)]
+    pub repair_fee: Option<Asset>,
+
+    /// Number of broken NFTs burned to forge a new NFT, forging is disabled while not set
+    #[serde(default)]
+    pub forge_inputs: Option<u32>,
+}
+
+fn default_unbonding_period() -> u64 {
+    DEFAULT_UNBONDING_PERIOD
+}
+
+/// Rewards harvested and distributed to the NFTs in a single harvest
+#[cw_serde]
+pub struct Harvest {
+    pub time: Timestamp,
+    /// LUNA rewards bonded for the LST
+    pub luna_bonded: Uint128,
+    /// LST received from bonding
+    pub lst_received: Uint128,
+    /// LST sent to the DAO treasury
+    pub treasury_amount: Uint128,
+    /// LST paid to the keeper that triggered the harvest
+    pub keeper_fee: Uint128,
+    /// Increment of the reward index, the LST received by an NFT with a weight of 1
+    pub index_increment: Decimal256,
+    /// Sum of the weights of the active NFTs at the time of the harvest
+    pub active_weight: u64,
+}
+
+/// Harvest waiting for the reply of the bond message to the LST hub
+#[cw_serde]
+pub struct PendingHarvest {
+    /// LUNA rewards bonded for the LST
+    pub luna_bonded: Uint128,
+    /// Caller of AllianceClaimRewards receiving the keeper fee
+    pub keeper: Option<Addr>,
+}
+
+/// Swap of the held LST into a new LST waiting for its reply
+#[cw_serde]
+pub struct LstSwitch {
+    pub previous_lst_asset_info: AssetInfo,
+    /// Amount of the previous LST sent to the swap
+    pub swapped: Uint128,
+    /// Balance of the new LST before the swap
+    pub balance_before: Uint128,
+    pub min_received: Uint128,
+}
+
+/// Virtual tokens undelegated from a validator that return to the contract at the completion time
+#[cw_serde]
+pub struct Unbonding {
+    pub validator: String,
+    pub amount: Uint128,
+    pub initiated_at: Timestamp,
+    pub completion_time: Timestamp,
+}
+
+/// Cumulative rewards per unit of weight of the active NFTs, kept with the precision
+/// of a Decimal256 so that no rewards are lost when they are split between the NFTs.
+/// The index and the checkpoints of the NFTs are kept in units of the LST at the time of the
+/// first distribution, the scale converts them into the LST that is held by the contract now.
+#[cw_serde]
+pub struct RewardIndex {
+    /// Sum of the rewards distributed to each unit of weight
+    pub index: Decimal256,
+    /// Rewards not distributed yet, carried over to the next distribution. These are the
+    /// fractions below the precision of the index, or all rewards while no NFT is active.
+    pub remainder: Decimal256,
+    /// Rewards paid out per unit of the index, changed when the rewards are converted into another LST
+    #[serde(default = "Decimal256::one")]
+    pub scale: Decimal256,
+}
+
+impl Default for RewardIndex {
+    fn default() -> Self {
+        RewardIndex {
+            index: Decimal256::zero(),
+            remainder: Decimal256::zero(),
+            scale: Decimal256::one(),
+        }
+    }
+}
+
+impl RewardIndex {
+    // split the rewards and the carried over remainder between the weight of the active NFTs,
+    // returning the increment of the rewards per unit of weight. Without active NFTs the rewards
+    // are kept undistributed for the NFTs that are active on the next distribution.
+    pub fn distribute(
+        &mut self,
+        rewards: Uint128,
+        total_weight: u64,
+    ) -> Result<Decimal256, ContractError> {
+        let total = self
+            .remainder
+            .checked_add(Decimal256::from_ratio(rewards, 1u128))?;
+        if total_weight == 0 {
+            self.remainder = total;
+            return Ok(Decimal256::zero());
+        }
+        let total_weight = Uint256::from(total_weight);
+
+        let total_in_index = total
+            .checked_div(self.scale)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+        let increment = Decimal256::new(total_in_index.atomics() / total_weight);
+        let distributed = increment
+            .checked_mul(Decimal256::from_ratio(total_weight, 1u128))?
+            .checked_mul(self.scale)?;
+        self.remainder = total.checked_sub(distributed)?;
+        self.index = self.index.checked_add(increment)?;
+        Ok(increment.checked_mul(self.scale)?)
+    }
+
+    // converts the rewards into another LST, of which ratio are received for each LST held
+    pub fn rescale(&mut self, ratio: Decimal256) -> StdResult<()> {
+        self.scale = self.scale.checked_mul(ratio)?;
+        self.remainder = self.remainder.checked_mul(ratio)?;
+        Ok(())
+    }
+
+    // takes the whole rewards out of the undistributed remainder
+    pub fn take_remainder(&mut self) -> StdResult<Uint128> {
+        let amount = Uint128::try_from(self.remainder.to_uint_floor())
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+        self.remainder = self
+            .remainder
+            .checked_sub(Decimal256::from_ratio(amount, 1u128))?;
+        Ok(amount)
+    }
+
+    // whole rewards accrued by an NFT of the given weight since the checkpoint
+    pub fn pending(&self, checkpoint: Decimal256, weight: u64) -> StdResult<Uint128> {
+        let accrued = self.accrued(checkpoint, weight)?.checked_mul(self.scale)?;
+        Uint128::try_from(accrued.to_uint_floor())
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    // returns the whole rewards accrued since the checkpoint and the new checkpoint,
+    // which keeps the fraction of the rewards that could not be paid out
+    pub fn claim(&self, checkpoint: Decimal256, weight: u64) -> StdResult<(Uint128, Decimal256)> {
+        let rewards = self.pending(checkpoint, weight)?;
+        // rounding up the rewards per unit of weight never pays out more than accrued
+        let divisor = Uint256::from(weight).checked_mul(self.scale.atomics())?;
+        let rewards_per_weight = Decimal256::new(
+            (Uint256::from(rewards)
+                .checked_mul(Decimal256::one().atomics())?
+                .checked_mul(Decimal256::one().atomics())?
+                + divisor
+                - Uint256::one())
+                / divisor,
+        );
+        let checkpoint = checkpoint.checked_add(rewards_per_weight)?;
+        Ok((rewards, checkpoint))
+    }
+
+    // returns the checkpoint that keeps the rewards accrued with the old weight
+    // pending after the weight of the NFT changed to the new weight, or None if
+    // the index is too low to hold them with the new weight
+    pub fn reweight(
+        &self,
+        checkpoint: Decimal256,
+        old_weight: u64,
+        new_weight: u64,
+    ) -> StdResult<Option<Decimal256>> {
+        let accrued = self.accrued(checkpoint, old_weight)?;
+        let weight = Uint256::from(new_weight);
+        // rounding up keeps the whole rewards pending, unless the rounding adds a whole unit
+        let floor = Decimal256::new(accrued.atomics() / weight);
+        let ceil = Decimal256::new((accrued.atomics() + weight - Uint256::one()) / weight);
+        let with_ceil = ceil
+            .checked_mul(Decimal256::from_ratio(new_weight, 1u128))?
+            .checked_mul(self.scale)?;
+        let accrued_per_weight =
+            if with_ceil.to_uint_floor() > accrued.checked_mul(self.scale)?.to_uint_floor() {
+                floor
+            } else {
+                ceil
+            };
+        Ok(self.index.checked_sub(accrued_per_weight).ok())
+    }
+
+    // rewards accrued since the checkpoint in units of the index
+    fn accrued(&self, checkpoint: Decimal256, weight: u64) -> StdResult<Decimal256> {
+        Ok(self
+            .index
+            .checked_sub(checkpoint)?
+            .checked_mul(Decimal256::from_ratio(weight, 1u128))?)
+    }
 }
 
 #[cw_serde]
@@ -109,20 +341,12 @@ impl MinterConfig {
 }
 
 #[cw_serde]
+#[derive(Default)]
 pub struct MinterStats {
     pub available_nfts: i16,
     pub minted_nfts: i16,
 }
 
-impl MinterStats {
-    pub fn default() -> MinterStats {
-        MinterStats {
-            available_nfts: 0,
-            minted_nfts: 0,
-        }
-    }
-}
-
 // Model necessary because the nfts are sorted by the token_id
 // the ones that scored more points should have a lower token_id ...
 #[cw_serde]